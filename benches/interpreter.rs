@@ -0,0 +1,75 @@
+//! Throughput baselines for the lexer, parser and evaluator.
+//!
+//! Run with `cargo bench`. This uses a tiny std-only harness instead of an
+//! external framework: each case runs a few warm-up iterations, then reports
+//! the mean wall time per iteration over a fixed number of runs.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use guedzlang::evaluator::Evaluator;
+use guedzlang::lexer::Lexer;
+use guedzlang::parser::Parser;
+use guedzlang::token::TokenKind;
+
+const STATEMENTS: usize = 10_000;
+const WARMUP: u32 = 3;
+const ITERATIONS: u32 = 20;
+
+/// Builds a deterministic program of `statements` top-level statements that
+/// cycles through every kind of literal and expression the parser supports.
+pub fn synthetic_source(statements: usize) -> String {
+    let mut source = String::new();
+    for i in 0..statements {
+        let name = identifier(i);
+        let line = match i % 5 {
+            0 => format!("let {name} = {i} * 2 + (3 - 1) / 1;\n"),
+            1 => format!("let {name} = fn(x, y) {{ if (x < y) {{ x }} else {{ y }} }};\n"),
+            2 => format!("let {name} = \"value\" + \"{name}\";\n"),
+            3 => format!("let {name} = [1, 2, {i}][1];\n"),
+            _ => format!("let {name} = {{\"key\": {i}, true: !false}};\n"),
+        };
+        source.push_str(&line);
+    }
+    source
+}
+
+/// Identifiers cannot start with a digit, so the index follows a `v_` prefix.
+fn identifier(index: usize) -> String {
+    format!("v_{index}")
+}
+
+fn bench(name: &str, mut run: impl FnMut()) {
+    for _ in 0..WARMUP {
+        run();
+    }
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    let mean: Duration = start.elapsed() / ITERATIONS;
+    println!("{name:<24} {mean:>12.3?} / iter");
+}
+
+fn main() {
+    let source = synthetic_source(STATEMENTS);
+
+    bench("lex 10k statements", || {
+        let mut lexer = Lexer::new(black_box(&source));
+        while lexer.next_token().kind != TokenKind::EOF {}
+    });
+
+    bench("parse 10k statements", || {
+        let mut parser = Parser::new(Lexer::new(black_box(&source)));
+        let program = parser.parse_program();
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        black_box(program);
+    });
+
+    let eval_loop =
+        "let i = 0; let sum = 0; while (i < 10000) { let sum = sum + i * 2; let i = i + 1; } sum;";
+    bench("eval arithmetic loop", || {
+        let program = Parser::new(Lexer::new(eval_loop)).parse_program();
+        black_box(Evaluator::new().eval_program(program));
+    });
+}
@@ -1,26 +1,115 @@
-use crate::token::Token;
+use crate::parser::{default_precedence, PrecedenceLevel};
+use crate::token::{Token, TokenKind};
 
 use std::fmt;
+use std::rc::Rc;
 
-pub trait Node {
+pub trait Node: fmt::Display {
     fn token_literal(&self) -> String;
+
+    /// Renders the node as multi-line source, indenting nested blocks two spaces
+    /// per level. `indent` is the nesting level of the line the node starts on.
+    /// Leaf nodes print the same as their `Display` form.
+    fn pretty_print(&self, _indent: usize) -> String {
+        self.to_string()
+    }
+}
+
+fn indentation(level: usize) -> String {
+    "  ".repeat(level)
+}
+
+/// How tightly `expression` holds together when printed without parentheses,
+/// as a `PrecedenceLevel`. Literals, blocks and other expressions with their
+/// own delimiters never come apart.
+fn binding_power(expression: &ExpressionNode) -> u8 {
+    match expression {
+        ExpressionNode::Assign(_) => PrecedenceLevel::Assign as u8,
+        ExpressionNode::Infix(infix) => default_precedence(&infix.token.kind) as u8,
+        ExpressionNode::Prefix(_) => PrecedenceLevel::Prefix as u8,
+        ExpressionNode::Call(_) => PrecedenceLevel::Call as u8,
+        ExpressionNode::Index(_) => PrecedenceLevel::Index as u8,
+        _ => u8::MAX,
+    }
+}
+
+/// Pretty prints `expression` in a spot the parser only reads back whole when
+/// the expression binds at least as tightly as `min`, adding parentheses
+/// otherwise.
+fn operand(expression: &ExpressionNode, min: u8, indent: usize) -> String {
+    let printed = expression.pretty_print(indent);
+    if binding_power(expression) < min {
+        format!("({})", printed)
+    } else {
+        printed
+    }
+}
+
+/// The comments the lexer kept in front of `token`, one per line, each
+/// followed by the indentation of the line the token itself starts on.
+fn leading_comments(token: &Token, indent: usize) -> String {
+    token
+        .leading_trivia
+        .iter()
+        .map(|comment| format!("{}\n{}", comment, indentation(indent)))
+        .collect()
 }
 
 #[derive(Debug, Clone)]
 pub enum StatementNode {
     Let(LetStatement),
+    Destructure(DestructureStatement),
     Return(ReturnStatement),
     Expression(ExpressionStatement),
     Block(BlockStatement),
+    While(WhileStatement),
+    Break(BreakStatement),
+    Continue(ContinueStatement),
 }
 
 impl Node for StatementNode {
     fn token_literal(&self) -> String {
         match self {
             Self::Let(let_stmt) => let_stmt.token_literal(),
+            Self::Destructure(destructure) => destructure.token_literal(),
             Self::Return(return_stmt) => return_stmt.token_literal(),
             Self::Expression(expression_stmt) => expression_stmt.token_literal(),
             Self::Block(block_stmt) => block_stmt.token_literal(),
+            Self::While(while_stmt) => while_stmt.token_literal(),
+            Self::Break(break_stmt) => break_stmt.token_literal(),
+            Self::Continue(continue_stmt) => continue_stmt.token_literal(),
+        }
+    }
+
+    /// Prints any comments kept in front of the statement on their own lines
+    /// above it.
+    fn pretty_print(&self, indent: usize) -> String {
+        let statement = match self {
+            Self::Let(let_stmt) => let_stmt.pretty_print(indent),
+            Self::Destructure(destructure) => destructure.pretty_print(indent),
+            Self::Return(return_stmt) => return_stmt.pretty_print(indent),
+            Self::Expression(expression_stmt) => expression_stmt.pretty_print(indent),
+            Self::Block(block_stmt) => block_stmt.pretty_print(indent),
+            Self::While(while_stmt) => while_stmt.pretty_print(indent),
+            Self::Break(break_stmt) => break_stmt.pretty_print(indent),
+            Self::Continue(continue_stmt) => continue_stmt.pretty_print(indent),
+        };
+        format!("{}{}", leading_comments(self.token(), indent), statement)
+    }
+}
+
+impl StatementNode {
+    /// The token the statement starts with.
+    fn token(&self) -> &Token {
+        match self {
+            Self::Let(let_stmt) => &let_stmt.token,
+            Self::Destructure(destructure) => &destructure.token,
+            Self::Return(return_stmt) => &return_stmt.token,
+            Self::Expression(expression_stmt) => &expression_stmt.token,
+            Self::Block(block_stmt) => &block_stmt.token,
+            Self::While(while_stmt) => &while_stmt.token,
+            Self::Break(break_stmt) => &break_stmt.token,
+            Self::Continue(continue_stmt) => &continue_stmt.token,
         }
     }
 }
@@ -29,9 +118,13 @@ impl fmt::Display for StatementNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Let(let_stmt) => write!(f, "{}", let_stmt),
+            Self::Destructure(destructure) => write!(f, "{}", destructure),
             Self::Return(return_stmt) => write!(f, "{}", return_stmt),
             Self::Expression(expression_stmt) => write!(f, "{}", expression_stmt),
-            Self::Block(block_stmt) => write!(f, "{}", block_stmt),
+            Self::Block(block_stmt) => write!(f, "{{ {} }}", block_stmt),
+            Self::While(while_stmt) => write!(f, "{}", while_stmt),
+            Self::Break(break_stmt) => write!(f, "{}", break_stmt),
+            Self::Continue(continue_stmt) => write!(f, "{}", continue_stmt),
         }
     }
 }
@@ -43,16 +136,23 @@ pub enum ExpressionNode {
     None,
     IdentifierNode(Identifier),
     Integer(IntegerLiteral),
+    Float(FloatLiteral),
     Prefix(PrefixExpression),
     Infix(InfixExpression),
     BooleanNode(Boolean),
     IfExpressionNode(IfExpression),
     Function(FunctionLiteral),
+    Macro(MacroLiteral),
     Call(CallExpression),
     StringExp(StringLiteral),
+    Char(CharLiteral),
     Array(ArrayLiteral),
     Index(IndexExpression),
     Hash(HashLiteral),
+    /// A `{ ... }` block used as a value; it evaluates to its last statement.
+    BlockExp(BlockStatement),
+    Assign(AssignExpression),
+    Match(MatchExpression),
 }
 
 impl Node for ExpressionNode {
@@ -60,16 +160,46 @@ impl Node for ExpressionNode {
         match self {
             Self::IdentifierNode(identifirer) => identifirer.token_literal(),
             Self::Integer(integer) => integer.token_literal(),
+            Self::Float(float) => float.token_literal(),
             Self::Prefix(prefix_expression) => prefix_expression.token_literal(),
             Self::Infix(infix_expression) => infix_expression.token_literal(),
             Self::BooleanNode(boolean) => boolean.token_literal(),
             Self::IfExpressionNode(if_expression) => if_expression.token_literal(),
             Self::Function(function) => function.token_literal(),
+            Self::Macro(macro_lit) => macro_lit.token_literal(),
             Self::Call(call_expression) => call_expression.token_literal(),
             Self::StringExp(string_literal) => string_literal.token_literal(),
+            Self::Char(char_literal) => char_literal.token_literal(),
             Self::Array(array_literal) => array_literal.token_literal(),
             Self::Index(idx_exp) => idx_exp.token_literal(),
             Self::Hash(hash_literal) => hash_literal.token_literal(),
+            Self::BlockExp(block) => block.token_literal(),
+            Self::Assign(assign) => assign.token_literal(),
+            Self::Match(match_exp) => match_exp.token_literal(),
+            Self::None => String::new(),
+        }
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        match self {
+            Self::IdentifierNode(identifier) => identifier.pretty_print(indent),
+            Self::Integer(integer) => integer.pretty_print(indent),
+            Self::Float(float) => float.pretty_print(indent),
+            Self::Prefix(prefix_expression) => prefix_expression.pretty_print(indent),
+            Self::Infix(infix_expression) => infix_expression.pretty_print(indent),
+            Self::BooleanNode(boolean) => boolean.pretty_print(indent),
+            Self::IfExpressionNode(if_expression) => if_expression.pretty_print(indent),
+            Self::Function(function) => function.pretty_print(indent),
+            Self::Macro(macro_lit) => macro_lit.pretty_print(indent),
+            Self::Call(call_expression) => call_expression.pretty_print(indent),
+            Self::StringExp(string_literal) => string_literal.pretty_print(indent),
+            Self::Char(char_literal) => char_literal.pretty_print(indent),
+            Self::Array(array_literal) => array_literal.pretty_print(indent),
+            Self::Index(idx_exp) => idx_exp.pretty_print(indent),
+            Self::Hash(hash_literal) => hash_literal.pretty_print(indent),
+            Self::BlockExp(block) => block.pretty_print(indent),
+            Self::Assign(assign) => assign.pretty_print(indent),
+            Self::Match(match_exp) => match_exp.pretty_print(indent),
             Self::None => String::new(),
         }
     }
@@ -80,16 +210,22 @@ impl fmt::Display for ExpressionNode {
         match self {
             Self::IdentifierNode(identifier) => write!(f, "{}", identifier),
             Self::Integer(integer) => write!(f, "{}", integer),
+            Self::Float(float) => write!(f, "{}", float),
             Self::Prefix(prefix_expression) => write!(f, "{}", prefix_expression),
             Self::Infix(infix_expression) => write!(f, "{}", infix_expression),
             Self::BooleanNode(boolean) => write!(f, "{}", boolean),
             Self::IfExpressionNode(if_expression) => write!(f, "{}", if_expression),
             Self::Function(function) => write!(f, "{}", function),
+            Self::Macro(macro_lit) => write!(f, "{}", macro_lit),
             Self::Call(call_expression) => write!(f, "{}", call_expression),
             Self::StringExp(string_literal) => write!(f, "{}", string_literal),
+            Self::Char(char_literal) => write!(f, "{}", char_literal),
             Self::Array(array_literal) => write!(f, "{}", array_literal),
             Self::Index(idx_exp) => write!(f, "{}", idx_exp),
             Self::Hash(hash_literal) => write!(f, "{}", hash_literal),
+            Self::BlockExp(block) => write!(f, "{{ {} }}", block),
+            Self::Assign(assign) => write!(f, "{}", assign),
+            Self::Match(match_exp) => write!(f, "{}", match_exp),
             Self::None => write!(f, ""),
         }
     }
@@ -104,14 +240,26 @@ impl Node for Program {
         if !self.statements.is_empty() {
             match &self.statements[0] {
                 StatementNode::Let(let_stmt) => let_stmt.token_literal(),
+                StatementNode::Destructure(destructure) => destructure.token_literal(),
                 StatementNode::Return(return_stmt) => return_stmt.token_literal(),
                 StatementNode::Expression(expression_stmt) => expression_stmt.token_literal(),
                 StatementNode::Block(block_stmt) => block_stmt.token_literal(),
+                StatementNode::While(while_stmt) => while_stmt.token_literal(),
+                StatementNode::Break(break_stmt) => break_stmt.token_literal(),
+                StatementNode::Continue(continue_stmt) => continue_stmt.token_literal(),
             }
         } else {
             String::new()
         }
     }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        self.statements
+            .iter()
+            .map(|stmt| format!("{}{}", indentation(indent), stmt.pretty_print(indent)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 impl fmt::Display for Program {
@@ -129,12 +277,47 @@ impl fmt::Display for Program {
 pub struct LetStatement {
     pub token: Token,
     pub name: Identifier,
+    /// The optional `: type` after the name. Parsed but not yet enforced.
+    pub type_annotation: Option<Identifier>,
     pub value: ExpressionNode,
 }
 
 impl Node for LetStatement {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        if let ExpressionNode::None = self.value {
+            return format!(
+                "{} {}{};",
+                self.token_literal(),
+                self.name,
+                self.format_annotation()
+            );
+        }
+        format!(
+            "{} {}{} = {};",
+            self.token_literal(),
+            self.name,
+            self.format_annotation(),
+            self.value.pretty_print(indent)
+        )
+    }
+}
+
+impl LetStatement {
+    /// Whether this binding was declared with `const` and so cannot be
+    /// reassigned.
+    pub fn is_constant(&self) -> bool {
+        self.token.kind == TokenKind::Const
+    }
+
+    fn format_annotation(&self) -> String {
+        match &self.type_annotation {
+            Some(annotation) => format!(": {}", annotation),
+            None => String::new(),
+        }
     }
 }
 
@@ -144,23 +327,83 @@ impl fmt::Display for LetStatement {
         out.push_str(self.token_literal().as_str());
         out.push(' ');
         out.push_str(self.name.to_string().as_str());
-        out.push_str(" = ");
-        out.push_str(self.value.to_string().as_str());
+        out.push_str(self.format_annotation().as_str());
+        if !matches!(self.value, ExpressionNode::None) {
+            out.push_str(" = ");
+            out.push_str(self.value.to_string().as_str());
+        }
         out.push(';');
         write!(f, "{}", out)
     }
 }
 
+/// `let [a, b, ...rest] = value;`: binds the elements of an array to names.
+/// Without a rest name the array must have exactly as many elements as there
+/// are names; `rest` collects whatever is left over.
+#[derive(Debug, Default, Clone)]
+pub struct DestructureStatement {
+    pub token: Token,
+    pub names: Vec<Identifier>,
+    pub rest: Option<Identifier>,
+    pub value: ExpressionNode,
+}
+
+impl DestructureStatement {
+    /// Every name the pattern binds, the rest name last.
+    pub fn bound_names(&self) -> impl Iterator<Item = &Identifier> {
+        self.names.iter().chain(&self.rest)
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.token.kind == TokenKind::Const
+    }
+
+    fn format_pattern(&self) -> String {
+        let mut names: Vec<String> = self.names.iter().map(|name| name.to_string()).collect();
+        if let Some(rest) = &self.rest {
+            names.push(format!("...{}", rest));
+        }
+        format!("[{}]", names.join(", "))
+    }
+}
+
+impl Node for DestructureStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "{} {} = {};",
+            self.token_literal(),
+            self.format_pattern(),
+            self.value.pretty_print(indent)
+        )
+    }
+}
+
+impl fmt::Display for DestructureStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} = {};",
+            self.token_literal(),
+            self.format_pattern(),
+            self.value
+        )
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 
 pub struct Identifier {
     pub token: Token,
-    pub value: String,
+    pub value: Rc<str>,
 }
 
 impl Node for Identifier {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
     }
 }
 
@@ -178,7 +421,15 @@ pub struct ReturnStatement {
 
 impl Node for ReturnStatement {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "{} {};",
+            self.token_literal(),
+            self.return_value.pretty_print(indent)
+        )
     }
 }
 
@@ -201,7 +452,16 @@ pub struct ExpressionStatement {
 
 impl Node for ExpressionStatement {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let expression = self.expression.pretty_print(indent);
+        // expressions that end in a block read like statements, so no `;`
+        match self.expression {
+            ExpressionNode::IfExpressionNode(_) | ExpressionNode::Function(_) => expression,
+            _ => format!("{};", expression),
+        }
     }
 }
 
@@ -219,7 +479,7 @@ pub struct IntegerLiteral {
 
 impl Node for IntegerLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
     }
 }
 
@@ -229,6 +489,25 @@ impl fmt::Display for IntegerLiteral {
     }
 }
 
+#[derive(Debug, Default, Clone)]
+pub struct FloatLiteral {
+    pub token: Token,
+    pub value: f64,
+}
+
+impl Node for FloatLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl fmt::Display for FloatLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // `{:?}` keeps the `.0` of whole numbers, so `2.0` stays a float
+        write!(f, "{:?}", self.value)
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct PrefixExpression {
     pub token: Token,
@@ -238,7 +517,12 @@ pub struct PrefixExpression {
 
 impl Node for PrefixExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let right = operand(&self.right, PrecedenceLevel::Prefix as u8, indent);
+        format!("{}{}", self.operator, right)
     }
 }
 
@@ -262,7 +546,19 @@ pub struct InfixExpression {
 
 impl Node for InfixExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        // operators group to the left, so an equally tight right operand
+        // keeps its parentheses: `a - (b - c)`
+        let level = default_precedence(&self.token.kind) as u8;
+        format!(
+            "{} {} {}",
+            operand(&self.left, level, indent),
+            self.operator,
+            operand(&self.right, level + 1, indent)
+        )
     }
 }
 
@@ -278,6 +574,30 @@ impl fmt::Display for InfixExpression {
     }
 }
 
+/// `name = value`, rebinding an existing variable. Evaluates to `value`.
+#[derive(Debug, Clone)]
+pub struct AssignExpression {
+    pub token: Token,
+    pub name: Identifier,
+    pub value: Box<ExpressionNode>,
+}
+
+impl Node for AssignExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!("{} = {}", self.name, self.value.pretty_print(indent))
+    }
+}
+
+impl fmt::Display for AssignExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({} = {})", self.name, self.value)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Boolean {
     pub token: Token,
@@ -286,7 +606,7 @@ pub struct Boolean {
 
 impl Node for Boolean {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
     }
 }
 
@@ -305,7 +625,20 @@ pub struct IfExpression {
 
 impl Node for IfExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let mut out = format!(
+            "if ({}) {}",
+            self.condition.pretty_print(indent),
+            self.consequence.pretty_print(indent)
+        );
+        if let Some(alt) = &self.alternative {
+            out.push_str(" else ");
+            out.push_str(alt.pretty_print(indent).as_str());
+        }
+        out
     }
 }
 
@@ -324,6 +657,77 @@ impl fmt::Display for IfExpression {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct WhileStatement {
+    pub token: Token,
+    pub condition: ExpressionNode,
+    pub body: BlockStatement,
+}
+
+impl Node for WhileStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "while ({}) {}",
+            self.condition.pretty_print(indent),
+            self.body.pretty_print(indent)
+        )
+    }
+}
+
+impl fmt::Display for WhileStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "while{} {}", self.condition, self.body)
+    }
+}
+
+/// `break;` ends the innermost enclosing loop.
+#[derive(Debug, Default, Clone)]
+pub struct BreakStatement {
+    pub token: Token,
+}
+
+impl Node for BreakStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, _indent: usize) -> String {
+        format!("{};", self.token_literal())
+    }
+}
+
+impl fmt::Display for BreakStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};", self.token_literal())
+    }
+}
+
+/// `continue;` skips to the next iteration of the innermost enclosing loop.
+#[derive(Debug, Default, Clone)]
+pub struct ContinueStatement {
+    pub token: Token,
+}
+
+impl Node for ContinueStatement {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, _indent: usize) -> String {
+        format!("{};", self.token_literal())
+    }
+}
+
+impl fmt::Display for ContinueStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{};", self.token_literal())
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct BlockStatement {
     pub token: Token,
@@ -332,7 +736,23 @@ pub struct BlockStatement {
 
 impl Node for BlockStatement {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        if self.statements.is_empty() {
+            return String::from("{}");
+        }
+
+        let mut out = String::from("{\n");
+        for statement in &self.statements {
+            out.push_str(indentation(indent + 1).as_str());
+            out.push_str(statement.pretty_print(indent + 1).as_str());
+            out.push('\n');
+        }
+        out.push_str(indentation(indent).as_str());
+        out.push('}');
+        out
     }
 }
 
@@ -350,11 +770,39 @@ pub struct FunctionLiteral {
     pub token: Token,
     pub parameters: Vec<Identifier>,
     pub body: BlockStatement,
+    /// When set, the last parameter is a `...rest` parameter collecting any
+    /// extra arguments into an array.
+    pub variadic: bool,
+}
+
+/// Joins parameter names with `, `, prefixing the rest parameter with `...`.
+pub fn format_parameters(parameters: &[Identifier], variadic: bool) -> String {
+    parameters
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            if variadic && i == parameters.len() - 1 {
+                format!("...{}", param)
+            } else {
+                param.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 impl Node for FunctionLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "{}({}) {}",
+            self.token_literal(),
+            format_parameters(&self.parameters, self.variadic),
+            self.body.pretty_print(indent)
+        )
     }
 }
 
@@ -363,12 +811,7 @@ impl fmt::Display for FunctionLiteral {
         let mut out = String::new();
         out.push_str(self.token_literal().as_str());
         out.push('(');
-        for (i, param) in self.parameters.iter().enumerate() {
-            out.push_str(param.to_string().as_str());
-            if i != self.parameters.len() - 1 {
-                out.push_str(", ");
-            }
-        }
+        out.push_str(format_parameters(&self.parameters, self.variadic).as_str());
         out.push(')');
         out.push(' ');
         out.push_str(self.body.to_string().as_str());
@@ -376,6 +819,42 @@ impl fmt::Display for FunctionLiteral {
     }
 }
 
+/// `macro(a, b) { ... }`. A top-level `let` binding one defines a macro whose
+/// calls are replaced by the quoted AST its body returns, before evaluation.
+#[derive(Debug, Clone)]
+pub struct MacroLiteral {
+    pub token: Token,
+    pub parameters: Vec<Identifier>,
+    pub body: BlockStatement,
+}
+
+impl Node for MacroLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "{}({}) {}",
+            self.token_literal(),
+            format_parameters(&self.parameters, false),
+            self.body.pretty_print(indent)
+        )
+    }
+}
+
+impl fmt::Display for MacroLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}({}) {}",
+            self.token_literal(),
+            format_parameters(&self.parameters, false),
+            self.body
+        )
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct CallExpression {
     pub token: Token,
@@ -385,7 +864,18 @@ pub struct CallExpression {
 
 impl Node for CallExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let args = self
+            .arguments
+            .iter()
+            .map(|arg| arg.pretty_print(indent))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let function = operand(&self.function, PrecedenceLevel::Call as u8, indent);
+        format!("{}({})", function, args)
     }
 }
 
@@ -409,11 +899,38 @@ impl fmt::Display for CallExpression {
 pub struct StringLiteral {
     pub token: Token,
     pub value: String,
+    /// Written with backticks: the value is taken verbatim, with no escapes
+    /// and no `{...}` interpolation.
+    pub raw: bool,
 }
 
 impl Node for StringLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    /// Quotes the value, escaping it so the output reads back as the same
+    /// string. Braces are left alone: an escaped brace is already doubled in
+    /// the value, so it still reads back as a literal one.
+    fn pretty_print(&self, _indent: usize) -> String {
+        if self.raw {
+            return format!("`{}`", self.value);
+        }
+        let mut quoted = String::with_capacity(self.value.len() + 2);
+        quoted.push('"');
+        for ch in self.value.chars() {
+            match ch {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                '\t' => quoted.push_str("\\t"),
+                '\r' => quoted.push_str("\\r"),
+                ch if ch.is_control() => quoted.push_str(&format!("\\u{{{:x}}}", ch as u32)),
+                ch => quoted.push(ch),
+            }
+        }
+        quoted.push('"');
+        quoted
     }
 }
 
@@ -423,6 +940,25 @@ impl fmt::Display for StringLiteral {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CharLiteral {
+    pub token: Token,
+    pub value: char,
+}
+
+impl Node for CharLiteral {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+}
+
+impl fmt::Display for CharLiteral {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // quoted and escaped the way it is written, e.g. `'\n'`
+        write!(f, "{:?}", self.value)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ArrayLiteral {
     pub token: Token,
@@ -431,7 +967,17 @@ pub struct ArrayLiteral {
 
 impl Node for ArrayLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let elements = self
+            .elements
+            .iter()
+            .map(|element| element.pretty_print(indent))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("[{}]", elements)
     }
 }
 
@@ -460,7 +1006,15 @@ pub struct IndexExpression {
 
 impl Node for IndexExpression {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        format!(
+            "{}[{}]",
+            operand(&self.left, PrecedenceLevel::Index as u8, indent),
+            self.index.pretty_print(indent)
+        )
     }
 }
 
@@ -484,7 +1038,23 @@ pub struct HashLiteral {
 
 impl Node for HashLiteral {
     fn token_literal(&self) -> String {
-        self.token.literal.clone()
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(key, value)| {
+                format!(
+                    "{}: {}",
+                    key.pretty_print(indent),
+                    value.pretty_print(indent)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{{{}}}", pairs)
     }
 }
 
@@ -503,9 +1073,72 @@ impl fmt::Display for HashLiteral {
     }
 }
 
+/// `match (subject) { pattern => value, ..., _ => value }`: yields the value
+/// of the first arm whose pattern equals the subject, or `null` if none does.
+#[derive(Debug, Clone)]
+pub struct MatchExpression {
+    pub token: Token,
+    pub subject: Box<ExpressionNode>,
+    pub arms: Vec<MatchArm>,
+}
+
+/// One arm of a `match`. A `None` pattern is the `_` wildcard.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Option<ExpressionNode>,
+    pub value: ExpressionNode,
+}
+
+impl MatchArm {
+    fn format_pattern(&self) -> String {
+        match &self.pattern {
+            Some(pattern) => pattern.to_string(),
+            None => String::from("_"),
+        }
+    }
+}
+
+impl Node for MatchExpression {
+    fn token_literal(&self) -> String {
+        self.token.literal.to_string()
+    }
+
+    fn pretty_print(&self, indent: usize) -> String {
+        let mut out = format!("match ({}) {{\n", self.subject.pretty_print(indent));
+        for arm in &self.arms {
+            let pattern = match &arm.pattern {
+                Some(pattern) => pattern.pretty_print(indent + 1),
+                None => String::from("_"),
+            };
+            out.push_str(&format!(
+                "{}{} => {},\n",
+                indentation(indent + 1),
+                pattern,
+                arm.value.pretty_print(indent + 1)
+            ));
+        }
+        out.push_str(&indentation(indent));
+        out.push('}');
+        out
+    }
+}
+
+impl fmt::Display for MatchExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arms: Vec<String> = self
+            .arms
+            .iter()
+            .map(|arm| format!("{} => {}", arm.format_pattern(), arm.value))
+            .collect();
+        write!(f, "match ({}) {{ {} }}", self.subject, arms.join(", "))
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{ExpressionNode, Identifier, LetStatement, Program, StatementNode};
+    use super::{ExpressionNode, Identifier, LetStatement, Node, Program, StatementNode};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
     use crate::token::{Token, TokenKind};
 
     #[test]
@@ -514,21 +1147,25 @@ mod test {
             statements: vec![StatementNode::Let(LetStatement {
                 token: Token {
                     kind: TokenKind::Let,
-                    literal: String::from("let"),
+                    literal: "let".into(),
+                    ..Default::default()
                 },
                 name: Identifier {
                     token: Token {
                         kind: TokenKind::Ident,
-                        literal: String::from("myVar"),
+                        literal: "myVar".into(),
+                        ..Default::default()
                     },
-                    value: String::from("myVar"),
+                    value: "myVar".into(),
                 },
+                type_annotation: None,
                 value: ExpressionNode::IdentifierNode(Identifier {
                     token: Token {
                         kind: TokenKind::Ident,
-                        literal: String::from("anotherVar"),
+                        literal: "anotherVar".into(),
+                        ..Default::default()
                     },
-                    value: String::from("anotherVar"),
+                    value: "anotherVar".into(),
                 }),
             })],
         };
@@ -540,4 +1177,47 @@ mod test {
             program
         );
     }
+
+    #[test]
+    fn test_pretty_print_nested_blocks() {
+        let input = r#"
+            let check = fn(x) { if (x) { return x; } else { let y = [1, "two"]; y } };
+            check(true);
+            fn(x) { if (x) { return x; } }
+        "#;
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "parser errors: {:?}",
+            parser.errors()
+        );
+
+        let expected = r#"let check = fn(x) {
+  if (x) {
+    return x;
+  } else {
+    let y = [1, "two"];
+    y;
+  }
+};
+check(true);
+fn(x) {
+  if (x) {
+    return x;
+  }
+}"#;
+
+        assert_eq!(
+            program.pretty_print(0),
+            expected,
+            "pretty printed program is wrong. got=\n{}",
+            program.pretty_print(0)
+        );
+
+        // the single-line form used by the precedence tests is unchanged
+        assert_eq!(program.statements[2].to_string(), "fn(x) ifx return x;");
+    }
 }
@@ -1,4 +1,5 @@
-use crate::object::{Object, NULL};
+use crate::evaluator::{EvalError, Evaluator};
+use crate::object::{HashKey, HashPair, HashStruct, Hashable, Object, FALSE, NULL, TRUE};
 
 pub struct Builtins;
 
@@ -11,103 +12,134 @@ impl Builtins {
             (String::from("rest"), Object::Builtin(b_rest)),
             (String::from("push"), Object::Builtin(b_push)),
             (String::from("log"), Object::Builtin(b_log)),
+            (String::from("map"), Object::Builtin(b_map)),
+            (String::from("reduce"), Object::Builtin(b_reduce)),
+            (String::from("filter"), Object::Builtin(b_filter)),
+            (String::from("apply"), Object::Builtin(b_apply)),
+            (String::from("try"), Object::Builtin(b_try)),
+            (String::from("slice"), Object::Builtin(b_slice)),
+            (String::from("contains"), Object::Builtin(b_contains)),
+            (String::from("abs"), Object::Builtin(b_abs)),
+            (String::from("min"), Object::Builtin(b_min)),
+            (String::from("max"), Object::Builtin(b_max)),
+            (String::from("range"), Object::Builtin(b_range)),
+            (String::from("keys"), Object::Builtin(b_keys)),
+            (String::from("values"), Object::Builtin(b_values)),
+            (String::from("delete"), Object::Builtin(b_delete)),
+            (String::from("assert"), Object::Builtin(b_assert)),
+            (String::from("chars"), Object::Builtin(b_chars)),
+            (String::from("to_chars"), Object::Builtin(b_to_chars)),
+            (String::from("from_chars"), Object::Builtin(b_from_chars)),
+            (String::from("upper"), Object::Builtin(b_upper)),
+            (String::from("lower"), Object::Builtin(b_lower)),
+            (String::from("trim"), Object::Builtin(b_trim)),
+            (String::from("replace"), Object::Builtin(b_replace)),
+            (String::from("index_of"), Object::Builtin(b_index_of)),
+            (String::from("globals"), Object::Builtin(b_globals)),
+            (String::from("ok"), Object::Builtin(b_ok)),
+            (String::from("err"), Object::Builtin(b_err)),
+            (String::from("some"), Object::Builtin(b_some)),
+            (String::from("none"), Object::Builtin(b_none)),
+            (String::from("is_ok"), Object::Builtin(b_is_ok)),
+            (String::from("is_err"), Object::Builtin(b_is_err)),
+            (String::from("unwrap"), Object::Builtin(b_unwrap)),
         ]
     }
 }
 
-fn b_len(args: Vec<Object>) -> Object {
+fn b_len(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     if args.len() != 1 {
-        return Object::Error(format!(
+        return Err(EvalError::new(format!(
             "wrong number of arguments. got={}, want=1",
             args.len()
-        ));
+        )));
     }
 
     match &args[0] {
-        Object::StringObj(string_lit) => Object::Integer(string_lit.len() as i64),
-        Object::Array(arr) => Object::Integer(arr.len() as i64),
-        other => Object::Error(format!(
+        Object::StringObj(string_lit) => Ok(Object::Integer(string_lit.len() as i64)),
+        Object::Array(arr) => Ok(Object::Integer(arr.len() as i64)),
+        other => Err(EvalError::new(format!(
             "argument to `len` not supported, got {}",
             other.object_type()
-        )),
+        ))),
     }
 }
 
-fn b_first(args: Vec<Object>) -> Object {
+fn b_first(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     if args.len() != 1 {
-        return Object::Error(format!(
+        return Err(EvalError::new(format!(
             "wrong number of arguments. got={}, want=1",
             args.len()
-        ));
+        )));
     }
 
     match &args[0] {
         Object::Array(arr) => {
             if !arr.is_empty() {
-                arr[0].clone()
+                Ok(arr[0].clone())
             } else {
-                NULL
+                Ok(NULL)
             }
         }
-        other => Object::Error(format!(
+        other => Err(EvalError::new(format!(
             "argument to `first` not supported, got {}",
             other.object_type()
-        )),
+        ))),
     }
 }
 
-fn b_last(args: Vec<Object>) -> Object {
+fn b_last(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     if args.len() != 1 {
-        return Object::Error(format!(
+        return Err(EvalError::new(format!(
             "wrong number of arguments. got={}, want=1",
             args.len()
-        ));
+        )));
     }
 
     match &args[0] {
         Object::Array(arr) => {
             if !arr.is_empty() {
-                arr[arr.len() - 1].clone()
+                Ok(arr[arr.len() - 1].clone())
             } else {
-                NULL
+                Ok(NULL)
             }
         }
-        other => Object::Error(format!(
+        other => Err(EvalError::new(format!(
             "argument to `last` not supported, got {}",
             other.object_type()
-        )),
+        ))),
     }
 }
 
-fn b_rest(args: Vec<Object>) -> Object {
+fn b_rest(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     if args.len() != 1 {
-        return Object::Error(format!(
+        return Err(EvalError::new(format!(
             "wrong number of arguments. got={}, want=1",
             args.len()
-        ));
+        )));
     }
 
     match &args[0] {
         Object::Array(arr) => {
             if !arr.is_empty() {
-                Object::Array(arr[1..].to_vec())
+                Ok(Object::Array(arr[1..].to_vec()))
             } else {
-                NULL
+                Ok(NULL)
             }
         }
-        other => Object::Error(format!(
+        other => Err(EvalError::new(format!(
             "argument to `rest` not supported, got {}",
             other.object_type()
-        )),
+        ))),
     }
 }
 
-fn b_push(args: Vec<Object>) -> Object {
+fn b_push(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     if args.len() != 2 {
-        return Object::Error(format!(
+        return Err(EvalError::new(format!(
             "wrong number of arguments. got={}, want=2",
             args.len()
-        ));
+        )));
     }
 
     match &args[0] {
@@ -115,21 +147,732 @@ fn b_push(args: Vec<Object>) -> Object {
             if !arr.is_empty() {
                 let mut new_elements = arr.clone();
                 new_elements.push(args[1].clone());
-                Object::Array(new_elements)
+                Ok(Object::Array(new_elements))
             } else {
-                NULL
+                Ok(NULL)
             }
         }
-        other => Object::Error(format!(
+        other => Err(EvalError::new(format!(
             "argument to `rest` not supported, got {}",
             other.object_type()
-        )),
+        ))),
     }
 }
 
-fn b_log(args: Vec<Object>) -> Object {
+fn b_log(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
     for arg in args {
         println!("{}", arg);
     }
-    NULL
+    Ok(NULL)
+}
+
+fn b_map(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Array(arr), Object::Func(_) | Object::Builtin(_)) => {
+            let mut mapped = Vec::with_capacity(arr.len());
+            for element in arr {
+                mapped.push(evaluator.apply_function(args[1].clone(), vec![element.clone()])?);
+            }
+            Ok(Object::Array(mapped))
+        }
+        (Object::Array(_), other) => Err(EvalError::new(format!(
+            "second argument to `map` must be FUNCTION, got {}",
+            other.object_type()
+        ))),
+        (other, _) => Err(EvalError::new(format!(
+            "argument to `map` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn b_reduce(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 3 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[2]) {
+        (Object::Array(arr), Object::Func(_) | Object::Builtin(_)) => {
+            let mut accumulator = args[1].clone();
+            for element in arr {
+                accumulator = evaluator
+                    .apply_function(args[2].clone(), vec![accumulator, element.clone()])?;
+            }
+            Ok(accumulator)
+        }
+        (Object::Array(_), other) => Err(EvalError::new(format!(
+            "third argument to `reduce` must be FUNCTION, got {}",
+            other.object_type()
+        ))),
+        (other, _) => Err(EvalError::new(format!(
+            "argument to `reduce` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn b_filter(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Array(arr), Object::Func(_) | Object::Builtin(_)) => {
+            let mut kept = Vec::new();
+            for element in arr {
+                let result = evaluator.apply_function(args[1].clone(), vec![element.clone()])?;
+                if Evaluator::is_truthy(result) {
+                    kept.push(element.clone());
+                }
+            }
+            Ok(Object::Array(kept))
+        }
+        (Object::Array(_), other) => Err(EvalError::new(format!(
+            "second argument to `filter` must be FUNCTION, got {}",
+            other.object_type()
+        ))),
+        (other, _) => Err(EvalError::new(format!(
+            "argument to `filter` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// `apply(f, args)`: calls `f` with the elements of `args` as its
+/// arguments, exactly as `f(args[0], args[1], ...)` would.
+fn b_apply(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Func(_) | Object::Builtin(_), Object::Array(arguments)) => {
+            evaluator.apply_function(args[0].clone(), arguments.clone())
+        }
+        (Object::Func(_) | Object::Builtin(_), other) => Err(EvalError::new(format!(
+            "second argument to `apply` must be ARRAY, got {}",
+            other.object_type()
+        ))),
+        (other, _) => Err(EvalError::new(format!(
+            "first argument to `apply` must be FUNCTION, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// `try(f)`: calls the zero-argument function `f` and returns its result. A
+/// runtime error raised inside `f` is returned as an error value, position
+/// included, instead of aborting the program.
+fn b_try(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::Func(_) | Object::Builtin(_) => {
+            match evaluator.apply_function(args[0].clone(), vec![]) {
+                Ok(result) => Ok(result),
+                Err(err) => Ok(Object::Error(err.to_string())),
+            }
+        }
+        other => Err(EvalError::new(format!(
+            "argument to `try` must be FUNCTION, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn b_slice(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 3 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1], &args[2]) {
+        (Object::Array(arr), Object::Integer(start), Object::Integer(end)) => {
+            let start = clamp_index(*start, arr.len());
+            let end = clamp_index(*end, arr.len());
+            if start >= end {
+                return Ok(Object::Array(Vec::new()));
+            }
+            Ok(Object::Array(arr[start..end].to_vec()))
+        }
+        (Object::Array(_), start, end) => Err(EvalError::new(format!(
+            "slice indices must be INTEGER, got {} and {}",
+            start.object_type(),
+            end.object_type()
+        ))),
+        (other, _, _) => Err(EvalError::new(format!(
+            "argument to `slice` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// Resolves a possibly negative index against `len`, clamping it into `0..=len`.
+fn clamp_index(idx: i64, len: usize) -> usize {
+    let len = len as i64;
+    let idx = if idx < 0 { idx + len } else { idx };
+    idx.clamp(0, len) as usize
+}
+
+fn b_contains(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Array(arr), needle) => Ok(Object::Boolean(arr.contains(needle))),
+        (Object::StringObj(haystack), Object::StringObj(needle)) => {
+            Ok(Object::Boolean(haystack.contains(needle.as_str())))
+        }
+        (Object::StringObj(_), other) => Err(EvalError::new(format!(
+            "needle for `contains` must be STRING, got {}",
+            other.object_type()
+        ))),
+        (other, _) => Err(EvalError::new(format!(
+            "argument to `contains` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn b_abs(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::Integer(value) => match value.checked_abs() {
+            Some(abs) => Ok(Object::Integer(abs)),
+            None => Err(EvalError::new(format!("integer overflow: abs({})", value))),
+        },
+        other => Err(EvalError::new(format!(
+            "argument to `abs` must be INTEGER, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn b_min(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    integer_pair("min", args, |a, b| a.min(b))
+}
+
+fn b_max(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    integer_pair("max", args, |a, b| a.max(b))
+}
+
+/// `range(end)` or `range(start, end)`: the integers from `start` (default 0)
+/// up to but excluding `end`. Each element counts against the evaluator's
+/// execution budget, so a huge range fails instead of exhausting memory.
+fn b_range(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    let (start, end) = match args.as_slice() {
+        [Object::Integer(end)] => (0, *end),
+        [Object::Integer(start), Object::Integer(end)] => (*start, *end),
+        [_] | [_, _] => {
+            let types: Vec<&str> = args.iter().map(Object::object_type).collect();
+            return Err(EvalError::new(format!(
+                "arguments to `range` must be INTEGER, got {}",
+                types.join(" and ")
+            )));
+        }
+        _ => {
+            return Err(EvalError::new(format!(
+                "wrong number of arguments. got={}, want=1 or 2",
+                args.len()
+            )))
+        }
+    };
+
+    if start >= end {
+        return Ok(Object::Array(Vec::new()));
+    }
+    let len = end.abs_diff(start) as usize;
+    evaluator.charge(len)?;
+    // without a budget nothing else stops a length no allocator can satisfy
+    let mut elements = Vec::new();
+    if elements.try_reserve_exact(len).is_err() {
+        return Err(EvalError::new(format!("range too large: {} elements", len)));
+    }
+    elements.extend((start..end).map(Object::Integer));
+    Ok(Object::Array(elements))
+}
+
+fn b_keys(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    hash_entries("keys", args, |pair| pair.key.clone())
+}
+
+fn b_values(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    hash_entries("values", args, |pair| pair.value.clone())
+}
+
+/// Maps every pair of a hash argument, in insertion order, into an array.
+fn hash_entries(
+    name: &str,
+    args: Vec<Object>,
+    entry: fn(&HashPair) -> Object,
+) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::HashObj(hash) => Ok(Object::Array(hash.iter().map(entry).collect())),
+        other => Err(EvalError::new(format!(
+            "argument to `{}` not supported, got {}",
+            name,
+            other.object_type()
+        ))),
+    }
+}
+
+/// Returns a copy of the hash without `key`; the original is left untouched.
+fn b_delete(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::HashObj(hash) => {
+            let key = args[1].hash_key().map_err(EvalError::new)?;
+            let mut hash = hash.clone();
+            hash.remove(&key);
+            Ok(Object::HashObj(hash))
+        }
+        other => Err(EvalError::new(format!(
+            "argument to `delete` not supported, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// `assert(cond)` / `assert(cond, message)`: `null` when `cond` is truthy (by
+/// the same rules as `if`), otherwise an "assertion failed" error.
+fn b_assert(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1 or 2",
+            args.len()
+        )));
+    }
+
+    let mut args = args.into_iter();
+    let condition = args.next().unwrap_or(NULL);
+    let message = match args.next() {
+        None => None,
+        Some(Object::StringObj(message)) => Some(message),
+        Some(other) => {
+            return Err(EvalError::new(format!(
+                "second argument to `assert` must be STRING, got {}",
+                other.object_type()
+            )))
+        }
+    };
+
+    if Evaluator::is_truthy(condition) {
+        return Ok(NULL);
+    }
+    Err(EvalError::new(match message {
+        Some(message) => format!("assertion failed: {}", message),
+        None => String::from("assertion failed"),
+    }))
+}
+
+/// `chars(s)`: the characters of `s` as an array of one-character strings.
+/// Splits on Unicode scalar values, so multi-byte characters stay whole.
+fn b_chars(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::StringObj(string) => Ok(Object::Array(
+            string
+                .chars()
+                .map(|ch| Object::StringObj(ch.to_string()))
+                .collect(),
+        )),
+        other => Err(EvalError::new(format!(
+            "argument to `chars` must be STRING, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// `to_chars(s)`: the characters of `s` as an array of CHAR objects, the
+/// inverse of `from_chars`.
+fn b_to_chars(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::StringObj(string) => Ok(Object::Array(string.chars().map(Object::Char).collect())),
+        other => Err(EvalError::new(format!(
+            "argument to `to_chars` must be STRING, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+/// `from_chars(arr)`: the string made of the CHAR objects in `arr`, in order.
+fn b_from_chars(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    let elements = match &args[0] {
+        Object::Array(elements) => elements,
+        other => {
+            return Err(EvalError::new(format!(
+                "argument to `from_chars` must be ARRAY, got {}",
+                other.object_type()
+            )))
+        }
+    };
+
+    elements
+        .iter()
+        .map(|element| match element {
+            Object::Char(ch) => Ok(*ch),
+            other => Err(EvalError::new(format!(
+                "elements of the array passed to `from_chars` must be CHAR, got {}",
+                other.object_type()
+            ))),
+        })
+        .collect::<Result<String, EvalError>>()
+        .map(Object::StringObj)
+}
+
+fn b_upper(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    string_transform("upper", args, str::to_uppercase)
+}
+
+fn b_lower(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    string_transform("lower", args, str::to_lowercase)
+}
+
+/// `trim(s)` strips leading and trailing whitespace; `trim(s, set)` strips
+/// any of the characters in `set` instead.
+fn b_trim(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1 or 2",
+            args.len()
+        )));
+    }
+
+    let string = match &args[0] {
+        Object::StringObj(string) => string,
+        other => {
+            return Err(EvalError::new(format!(
+                "argument to `trim` must be STRING, got {}",
+                other.object_type()
+            )))
+        }
+    };
+    let trimmed = match args.get(1) {
+        None => string.trim(),
+        Some(Object::StringObj(set)) => string.trim_matches(|ch| set.contains(ch)),
+        Some(other) => {
+            return Err(EvalError::new(format!(
+                "second argument to `trim` must be STRING, got {}",
+                other.object_type()
+            )))
+        }
+    };
+    Ok(Object::StringObj(trimmed.to_string()))
+}
+
+/// `replace(s, from, to)`: `s` with every occurrence of `from` replaced by
+/// `to`. An empty `from` is an error, since it would match everywhere.
+fn b_replace(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 3 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=3",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1], &args[2]) {
+        (Object::StringObj(_), Object::StringObj(from), Object::StringObj(_))
+            if from.is_empty() =>
+        {
+            Err(EvalError::new(String::from(
+                "pattern for `replace` must not be empty",
+            )))
+        }
+        (Object::StringObj(string), Object::StringObj(from), Object::StringObj(to)) => {
+            Ok(Object::StringObj(string.replace(from.as_str(), to)))
+        }
+        (a, b, c) => Err(EvalError::new(format!(
+            "arguments to `replace` must be STRING, got {}, {} and {}",
+            a.object_type(),
+            b.object_type(),
+            c.object_type()
+        ))),
+    }
+}
+
+/// `index_of(x, y)`: where `y` first occurs in `x`, or `-1` if it does not.
+/// For an array that is the first element equal to `y`; for a string it is
+/// the start of substring `y`, counted in characters so it lines up with
+/// `chars(x)`.
+fn b_index_of(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    let index = match (&args[0], &args[1]) {
+        (Object::Array(arr), needle) => arr.iter().position(|element| element == needle),
+        (Object::StringObj(haystack), Object::StringObj(needle)) => haystack
+            .find(needle.as_str())
+            .map(|byte_index| haystack[..byte_index].chars().count()),
+        (Object::StringObj(_), other) => {
+            return Err(EvalError::new(format!(
+                "needle for `index_of` must be STRING, got {}",
+                other.object_type()
+            )))
+        }
+        (other, _) => {
+            return Err(EvalError::new(format!(
+                "argument to `index_of` not supported, got {}",
+                other.object_type()
+            )))
+        }
+    };
+    Ok(Object::Integer(index.map_or(-1, |index| index as i64)))
+}
+
+/// `globals()`: the names bound in the active scope and every scope around
+/// it, sorted. Builtins are left out.
+fn b_globals(evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if !args.is_empty() {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=0",
+            args.len()
+        )));
+    }
+
+    let names = evaluator.env().borrow().visible_names();
+    Ok(Object::Array(
+        names.into_iter().map(Object::StringObj).collect(),
+    ))
+}
+
+/// `ok(x)`: a successful result holding `x`. Results and options are plain
+/// hashes tagged under `"__variant__"`, e.g. `{"__variant__": "ok", "value": x}`.
+fn b_ok(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    tagged("ok", "value", args)
+}
+
+/// `err(e)`: a failed result holding the error `e`, under `"error"`.
+fn b_err(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    tagged("err", "error", args)
+}
+
+/// `some(x)`: an option holding `x`.
+fn b_some(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    tagged("some", "value", args)
+}
+
+/// `none()`: the empty option.
+fn b_none(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if !args.is_empty() {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=0",
+            args.len()
+        )));
+    }
+
+    let mut hash = HashStruct::new();
+    insert_string_key(
+        &mut hash,
+        VARIANT_KEY,
+        Object::StringObj(String::from("none")),
+    );
+    Ok(Object::HashObj(hash))
+}
+
+fn b_is_ok(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    has_variant("ok", args)
+}
+
+/// `is_err(x)`: whether `x` is an `err`, or an error value caught by `try`.
+fn b_is_err(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if let [Object::Error(_)] = args.as_slice() {
+        return Ok(TRUE);
+    }
+    has_variant("err", args)
+}
+
+/// `unwrap(x)`: the value inside an `ok` or `some`. Unwrapping an `err` or
+/// `none` is a runtime error.
+fn b_unwrap(_evaluator: &mut Evaluator, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match (variant_of(&args[0]).as_deref(), &args[0]) {
+        (Some("ok" | "some"), Object::HashObj(hash)) => Ok(string_key(hash, "value")),
+        (Some("err"), Object::HashObj(hash)) => Err(EvalError::new(format!(
+            "unwrap called on err: {}",
+            string_key(hash, "error")
+        ))),
+        (Some("none"), _) => Err(EvalError::new(String::from("unwrap called on none"))),
+        (_, other) => Err(EvalError::new(format!(
+            "argument to `unwrap` must be a result or option, got {}",
+            other.object_type()
+        ))),
+    }
+}
+
+fn integer_pair(
+    name: &str,
+    args: Vec<Object>,
+    op: fn(i64, i64) -> i64,
+) -> Result<Object, EvalError> {
+    if args.len() != 2 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=2",
+            args.len()
+        )));
+    }
+
+    match (&args[0], &args[1]) {
+        (Object::Integer(a), Object::Integer(b)) => Ok(Object::Integer(op(*a, *b))),
+        (a, b) => Err(EvalError::new(format!(
+            "arguments to `{}` must be INTEGER, got {} and {}",
+            name,
+            a.object_type(),
+            b.object_type()
+        ))),
+    }
+}
+
+fn string_transform(
+    name: &str,
+    args: Vec<Object>,
+    op: fn(&str) -> String,
+) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        Object::StringObj(string) => Ok(Object::StringObj(op(string))),
+        other => Err(EvalError::new(format!(
+            "argument to `{}` must be STRING, got {}",
+            name,
+            other.object_type()
+        ))),
+    }
+}
+
+/// The key that tags a result or option hash with its variant.
+const VARIANT_KEY: &str = "__variant__";
+
+/// Builds `{"__variant__": tag, field: x}` from the single argument `x`.
+fn tagged(tag: &str, field: &str, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    let mut hash = HashStruct::new();
+    insert_string_key(&mut hash, VARIANT_KEY, Object::StringObj(tag.to_string()));
+    insert_string_key(&mut hash, field, args.into_iter().next().unwrap_or(NULL));
+    Ok(Object::HashObj(hash))
+}
+
+/// Whether the single argument is a result or option tagged `tag`.
+fn has_variant(tag: &str, args: Vec<Object>) -> Result<Object, EvalError> {
+    if args.len() != 1 {
+        return Err(EvalError::new(format!(
+            "wrong number of arguments. got={}, want=1",
+            args.len()
+        )));
+    }
+
+    Ok(match variant_of(&args[0]) {
+        Some(variant) if variant == tag => TRUE,
+        _ => FALSE,
+    })
+}
+
+/// The `"__variant__"` tag of a result or option hash.
+fn variant_of(object: &Object) -> Option<String> {
+    match object {
+        Object::HashObj(hash) => match string_key(hash, VARIANT_KEY) {
+            Object::StringObj(tag) => Some(tag),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn insert_string_key(hash: &mut HashStruct, key: &str, value: Object) {
+    let key = Object::StringObj(key.to_string());
+    hash.insert(HashKey::String(key.to_string()), HashPair { key, value });
+}
+
+/// The value under the string `key`, or `null`.
+fn string_key(hash: &HashStruct, key: &str) -> Object {
+    match hash.get(&HashKey::String(key.to_string())) {
+        Some(pair) => pair.value.clone(),
+        None => NULL,
+    }
 }
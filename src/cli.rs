@@ -0,0 +1,232 @@
+//! Command-line handling for the `guedzlang` binary. It lives in the library
+//! so argument parsing and one-shot evaluation can be tested without spawning
+//! a process.
+
+use std::fs;
+use std::io::{self, Read, Write};
+
+use crate::evaluator::Evaluator;
+use crate::lexer::Lexer;
+use crate::object::Object;
+use crate::parser::Parser;
+
+pub const USAGE: &str = "usage: guedzlang [--eval <code> | <file> | -]";
+
+/// Process exit codes, so scripts can tell failure modes apart. Bad arguments
+/// and unreadable files exit with 1.
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_PARSE_ERROR: i32 = 2;
+pub const EXIT_RUNTIME_ERROR: i32 = 3;
+
+/// What the binary was asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// No arguments: start the interactive REPL.
+    Repl,
+    /// `--eval <code>`: evaluate `code` once and exit.
+    Eval(String),
+    /// `<file>`: evaluate a source file; `-` reads the program from stdin.
+    File(String),
+}
+
+/// Parses the arguments that follow the program name.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    let command = match args.next().as_deref() {
+        None => Command::Repl,
+        Some("--eval") => match args.next() {
+            Some(code) => Command::Eval(code),
+            None => return Err(String::from("--eval needs a code argument")),
+        },
+        Some(flag) if flag.starts_with("--") => {
+            return Err(format!("unrecognized argument '{}'", flag))
+        }
+        Some(path) => Command::File(path.to_string()),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+        None => Ok(command),
+    }
+}
+
+/// Reads the program at `path`, or all of `stdin` when `path` is `-`, and runs
+/// it with [`run_source`]. An unreadable file is reported to `err` as a failure.
+pub fn run_file<R: Read, O: Write, E: Write>(
+    path: &str,
+    mut stdin: R,
+    out: &mut O,
+    err: &mut E,
+) -> io::Result<i32> {
+    let source = if path == "-" {
+        let mut source = String::new();
+        stdin.read_to_string(&mut source).map(|_| source)
+    } else {
+        fs::read_to_string(path)
+    };
+
+    match source {
+        Ok(source) => run_source(&source, out, err),
+        Err(error) => {
+            writeln!(err, "Could not read {}: {}", path, error)?;
+            Ok(1)
+        }
+    }
+}
+
+/// Evaluates `source` as a whole program. The result goes to `out` (nothing
+/// for `null`), parse and runtime errors go to `err`. An error value the
+/// program caught with `try` is a result like any other. Returns the process
+/// exit code: [`EXIT_SUCCESS`], [`EXIT_PARSE_ERROR`] or [`EXIT_RUNTIME_ERROR`].
+pub fn run_source<O: Write, E: Write>(source: &str, out: &mut O, err: &mut E) -> io::Result<i32> {
+    let mut parser = Parser::new(Lexer::new(source));
+    let program = parser.parse_program();
+
+    if !parser.errors().is_empty() {
+        for error in parser.errors() {
+            writeln!(err, "{}", error)?;
+        }
+        return Ok(EXIT_PARSE_ERROR);
+    }
+
+    match Evaluator::new().eval(program) {
+        Err(error) => {
+            writeln!(err, "ERROR: {}", error)?;
+            Ok(EXIT_RUNTIME_ERROR)
+        }
+        Ok(Object::Null) => Ok(EXIT_SUCCESS),
+        Ok(result) => {
+            writeln!(out, "{}", result.inspect())?;
+            Ok(EXIT_SUCCESS)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Cursor};
+
+    use super::{parse_args, run_file, run_source, Command};
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let tests = vec![
+            (args(&[]), Ok(Command::Repl)),
+            (
+                args(&["--eval", "2 + 2"]),
+                Ok(Command::Eval(String::from("2 + 2"))),
+            ),
+            (
+                args(&["--eval"]),
+                Err(String::from("--eval needs a code argument")),
+            ),
+            (
+                args(&["--eval", "1", "2"]),
+                Err(String::from("unexpected argument '2'")),
+            ),
+            (
+                args(&["script.gz"]),
+                Ok(Command::File(String::from("script.gz"))),
+            ),
+            (args(&["-"]), Ok(Command::File(String::from("-")))),
+            (
+                args(&["--verbose"]),
+                Err(String::from("unrecognized argument '--verbose'")),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(parse_args(input.clone()), expected, "args: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_run_source() {
+        let tests = vec![
+            ("let x = 2; x + 2", 0, "4\n", ""),
+            (r#""hi""#, 0, "\"hi\"\n", ""),
+            ("let x = 1;", 0, "1\n", ""),
+            ("if (false) { 1 }", 0, "", ""),
+            (
+                "1 + true",
+                3,
+                "",
+                "ERROR: [line 1, col 3] type mismatch: INTEGER + BOOLEAN\n",
+            ),
+            (
+                "undefined_name",
+                3,
+                "",
+                "ERROR: [line 1, col 1] identifier not found: undefined_name\n",
+            ),
+            // a caught error is a value, not a failure
+            (
+                "try(fn() { 1 / 0 })",
+                0,
+                "ERROR: [line 1, col 14] division by zero\n",
+                "",
+            ),
+            (
+                "let = 1;",
+                2,
+                "",
+                "expected next token to be Ident, got Assign instead\n\
+                 no prefix parse function for '=' found\n",
+            ),
+        ];
+
+        for (source, code, stdout, stderr) in tests {
+            let mut out = Vec::new();
+            let mut err = Vec::new();
+            let exit = run_source(source, &mut out, &mut err).unwrap();
+
+            assert_eq!(exit, code, "source: {}", source);
+            assert_eq!(
+                String::from_utf8(out).unwrap(),
+                stdout,
+                "source: {}",
+                source
+            );
+            assert_eq!(
+                String::from_utf8(err).unwrap(),
+                stderr,
+                "source: {}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_file_reads_stdin_for_dash() {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        let stdin = Cursor::new("let x = 1;\nx + 2\n");
+
+        let exit = run_file("-", stdin, &mut out, &mut err).unwrap();
+
+        assert_eq!(exit, 0);
+        assert_eq!(String::from_utf8(out).unwrap(), "3\n");
+        assert_eq!(String::from_utf8(err).unwrap(), "");
+    }
+
+    #[test]
+    fn test_run_file_missing_path() {
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+
+        let exit = run_file("/definitely/not/here.gz", io::empty(), &mut out, &mut err).unwrap();
+
+        assert_eq!(exit, 1);
+        let err = String::from_utf8(err).unwrap();
+        assert!(
+            err.starts_with("Could not read /definitely/not/here.gz: "),
+            "got: {}",
+            err
+        );
+    }
+}
@@ -1,181 +1,550 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{fmt, mem, ops::Deref};
 
 use crate::{
-    ast::{BlockStatement, ExpressionNode, Identifier, IfExpression, Program, StatementNode},
+    ast::{
+        BlockStatement, CallExpression, DestructureStatement, ExpressionNode, Identifier,
+        IfExpression, Node, Program, StatementNode, StringLiteral, WhileStatement,
+    },
+    lexer::Lexer,
+    macro_expansion::{define_macros, expand_macros},
     object::{
-        Env, Environment, Function, HashPair, HashStruct, Hashable, Object, FALSE, NULL, TRUE,
+        Env, Environment, Function, HashKey, HashPair, HashStruct, Hashable, Object, FALSE, NULL,
+        TRUE,
     },
+    optimizer::{boolean_node, integer_node, walk_expression, Rewrite},
+    parser::Parser,
+    token::Position,
 };
 
+/// A runtime error, tagged with the source position of the node that raised
+/// it when that is known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    pub message: String,
+    pub position: Option<Position>,
+    /// Not an error but a `return`, `break` or `continue` raised by a block
+    /// used as an operand. It unwinds like an error up to the enclosing
+    /// statement, which then evaluates to the signal.
+    signal: Option<Box<Object>>,
+}
+
+impl EvalError {
+    pub fn new(message: String) -> Self {
+        EvalError {
+            message,
+            position: None,
+            signal: None,
+        }
+    }
+
+    pub fn at(message: String, position: Position) -> Self {
+        EvalError {
+            message,
+            position: Some(position),
+            signal: None,
+        }
+    }
+
+    /// Fills in `position` unless the error already knows where it came from,
+    /// so an error raised deep inside a call keeps its original location.
+    pub(crate) fn or_at(self, position: Position) -> Self {
+        match self.position {
+            Some(_) => self,
+            None => EvalError {
+                position: Some(position),
+                ..self
+            },
+        }
+    }
+
+    /// Passes `result` on, unless it is a control signal, which is raised so
+    /// it can escape the expression it was produced in.
+    fn raise_signal(result: Object) -> Result<Object, EvalError> {
+        match result {
+            Object::ReturnValue(_) | Object::Break | Object::Continue => Err(EvalError {
+                message: String::new(),
+                position: None,
+                signal: Some(Box::new(result)),
+            }),
+            result => Ok(result),
+        }
+    }
+
+    /// The signal raised by [`EvalError::raise_signal`], back as the value of
+    /// its statement; real errors stay errors.
+    fn catch_signal(self) -> Result<Object, EvalError> {
+        match self.signal {
+            Some(signal) => Ok(*signal),
+            None => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "[{}] {}", position, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// How integer `/` and `%` round when the quotient is negative, and how float
+/// `%` picks its sign. Either way `(a / b) * b + a % b == a` holds for integers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DivMode {
+    /// Round toward zero, like Rust: `-7 / 2` is `-3` and `-7 % 2` is `-1`.
+    #[default]
+    Trunc,
+    /// Round toward negative infinity: `-7 / 2` is `-4` and `-7 % 2` is `1`.
+    Floor,
+}
+
 pub struct Evaluator {
     env: Env,
+    div_mode: DivMode,
+    budget: Option<usize>,
+    steps: usize,
+    /// How many loops enclose the code being run, within the current
+    /// function. `break` and `continue` are only valid when it is non-zero.
+    loop_depth: usize,
 }
 
 impl Evaluator {
     pub fn new() -> Self {
         Evaluator {
             env: Environment::new_environment(),
+            div_mode: DivMode::default(),
+            budget: None,
+            steps: 0,
+            loop_depth: 0,
         }
     }
 
-    pub fn eval_program(&mut self, program: Program) -> Object {
+    /// The current innermost scope. Between evaluations this is the global
+    /// scope; while a builtin runs it is the scope of the function or block
+    /// that called it.
+    pub fn env(&self) -> Env {
+        self.env.clone()
+    }
+
+    /// Caps how many AST nodes may be evaluated before evaluation fails with
+    /// "execution budget exceeded". `None` (the default) means unlimited.
+    /// The count accumulates across calls until [`Evaluator::reset_steps`].
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Chooses how integer division and modulo round; see [`DivMode`].
+    pub fn set_div_mode(&mut self, mode: DivMode) {
+        self.div_mode = mode;
+    }
+
+    /// Number of AST nodes evaluated since the last reset.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn reset_steps(&mut self) {
+        self.steps = 0;
+    }
+
+    fn tick(&mut self) -> Result<(), EvalError> {
+        self.charge(1)
+    }
+
+    /// Counts `steps` units of work against the budget. Builtins that do work
+    /// proportional to their input, such as `range`, charge for it here.
+    pub(crate) fn charge(&mut self, steps: usize) -> Result<(), EvalError> {
+        self.steps = self.steps.saturating_add(steps);
+        match self.budget {
+            Some(budget) if self.steps > budget => {
+                Err(EvalError::new(String::from("execution budget exceeded")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs every statement and returns the value of the last one. A `let`
+    /// evaluates to the value it binds, a top-level `return` stops early with
+    /// its value, and an empty program is `Null`. Macros are defined and
+    /// expanded first, see [`crate::macro_expansion`].
+    pub fn eval(&mut self, mut program: Program) -> Result<Object, EvalError> {
+        define_macros(&mut program, &self.env);
+        let program = expand_macros(program, self)?;
         let mut result = Object::Null;
 
         for stmt in program.statements {
-            result = self.eval_statement(stmt);
+            result = self.eval_statement(stmt)?;
 
             if let Object::ReturnValue(ret) = result {
-                return *ret;
-            }
-            if let Object::Error(_) = result {
-                return result;
+                return Ok(*ret);
             }
         }
-        result
+        Ok(result)
+    }
+
+    /// Like [`Evaluator::eval`], but reports a runtime error as an `Object::Error`
+    /// whose message is prefixed with its source position.
+    pub fn eval_program(&mut self, program: Program) -> Object {
+        self.eval(program)
+            .unwrap_or_else(|err| Object::Error(err.to_string()))
+    }
+
+    fn eval_statement(&mut self, stmt: StatementNode) -> Result<Object, EvalError> {
+        self.tick()?;
+        self.eval_statement_node(stmt)
+            .or_else(EvalError::catch_signal)
     }
 
-    fn eval_statement(&mut self, stmt: StatementNode) -> Object {
+    fn eval_statement_node(&mut self, stmt: StatementNode) -> Result<Object, EvalError> {
         match stmt {
             StatementNode::Expression(exp_stmt) => self.eval_expression(exp_stmt.expression),
             StatementNode::Return(ret_stmt) => {
-                let value = self.eval_expression(ret_stmt.return_value);
-                if Self::is_error(&value) {
-                    return value;
-                }
-                Object::ReturnValue(Box::new(value))
+                let value = self.eval_expression(ret_stmt.return_value)?;
+                Ok(Object::ReturnValue(Box::new(value)))
             }
             StatementNode::Let(let_stmt) => {
-                let value = self.eval_expression(let_stmt.value);
-                if Self::is_error(&value) {
-                    return value;
-                }
-                self.env
-                    .borrow_mut()
-                    .set(let_stmt.name.value, value.clone());
-                value
+                let constant = let_stmt.is_constant();
+                let value = self.eval_expression(let_stmt.value)?;
+                self.bind(let_stmt.name, value.clone(), constant)
+                    .map_err(|msg| EvalError::at(msg, let_stmt.token.position))?;
+                Ok(value)
+            }
+            StatementNode::Destructure(destructure) => self.eval_destructure_statement(destructure),
+            StatementNode::While(while_stmt) => self.eval_while_statement(while_stmt),
+            StatementNode::Block(block) => self.eval_scoped_block(block),
+            StatementNode::Break(break_stmt) => {
+                self.loop_signal(Object::Break, "break", break_stmt.token.position)
+            }
+            StatementNode::Continue(continue_stmt) => {
+                self.loop_signal(Object::Continue, "continue", continue_stmt.token.position)
+            }
+        }
+    }
+
+    /// Binds `name` in the current scope, as `let` (or `const` when `constant`)
+    /// does.
+    fn bind(&mut self, name: Identifier, value: Object, constant: bool) -> Result<(), String> {
+        let mut env = self.env.borrow_mut();
+
+        // redeclaring a constant in its own scope would sidestep `assign`
+        if env.is_constant(&name.value) {
+            return Err(format!("cannot assign to constant '{}'", name.value));
+        }
+        if constant {
+            env.set_constant(name.value, value);
+        } else {
+            env.set(name.value, value);
+        }
+        Ok(())
+    }
+
+    fn eval_destructure_statement(
+        &mut self,
+        destructure: DestructureStatement,
+    ) -> Result<Object, EvalError> {
+        let position = destructure.token.position;
+        let constant = destructure.is_constant();
+        let value = self.eval_expression(destructure.value)?;
+
+        let elements = match &value {
+            Object::Array(elements) => elements,
+            other => {
+                return Err(EvalError::at(
+                    format!("cannot destructure {}, expected ARRAY", other.object_type()),
+                    position,
+                ))
             }
-            _ => Object::Null,
+        };
+        let want = destructure.names.len();
+        let mismatch = match destructure.rest {
+            Some(_) => elements.len() < want,
+            None => elements.len() != want,
+        };
+        if mismatch {
+            return Err(EvalError::at(
+                format!(
+                    "cannot destructure an array of {} elements into {}{} names",
+                    elements.len(),
+                    if destructure.rest.is_some() {
+                        "at least "
+                    } else {
+                        ""
+                    },
+                    want
+                ),
+                position,
+            ));
+        }
+
+        let (fixed, rest) = elements.split_at(want);
+        for (name, element) in destructure.names.into_iter().zip(fixed) {
+            self.bind(name, element.clone(), constant)
+                .map_err(|msg| EvalError::at(msg, position))?;
         }
+        if let Some(rest_name) = destructure.rest {
+            self.bind(rest_name, Object::Array(rest.to_vec()), constant)
+                .map_err(|msg| EvalError::at(msg, position))?;
+        }
+        Ok(value)
     }
 
-    fn eval_expression(&mut self, expression: ExpressionNode) -> Object {
+    fn eval_expression(&mut self, expression: ExpressionNode) -> Result<Object, EvalError> {
+        self.tick()?;
+
         match expression {
-            ExpressionNode::Integer(int) => Object::Integer(int.value),
+            ExpressionNode::Integer(int) => Ok(Object::Integer(int.value)),
+            ExpressionNode::Float(float) => Ok(Object::Float(float.value)),
             ExpressionNode::BooleanNode(boolean) => {
-                Self::native_bool_to_boolean_object(boolean.value)
+                Ok(Self::native_bool_to_boolean_object(boolean.value))
             }
             ExpressionNode::Prefix(prefix_exp) => {
-                let right: Object = self.eval_expression(*prefix_exp.right);
-                if Self::is_error(&right) {
-                    return right;
-                }
+                let right: Object = self.eval_expression(*prefix_exp.right)?;
                 Self::eval_prefix_expression(&prefix_exp.operator, right)
+                    .map_err(|msg| EvalError::at(msg, prefix_exp.token.position))
             }
             ExpressionNode::Infix(inf_exp) => {
-                let left: Object = self.eval_expression(*inf_exp.left);
-                if Self::is_error(&left) {
-                    return left;
+                let left: Object = self.eval_expression(*inf_exp.left)?;
+                let right: Object = self.eval_expression(*inf_exp.right)?;
+                if let Some(method) = Self::operator_method(&inf_exp.operator, &left, &right) {
+                    return self
+                        .apply_function(method, vec![left, right])
+                        .map_err(|err| err.or_at(inf_exp.token.position));
                 }
-                let right: Object = self.eval_expression(*inf_exp.right);
-                if Self::is_error(&right) {
-                    return right;
+                self.eval_infix_expression(&inf_exp.operator, &left, &right)
+                    .map_err(|msg| EvalError::at(msg, inf_exp.token.position))
+            }
+            ExpressionNode::IfExpressionNode(if_exp) => self
+                .eval_if_expression(if_exp)
+                .and_then(EvalError::raise_signal),
+            ExpressionNode::Match(match_exp) => {
+                let subject = self.eval_expression(*match_exp.subject)?;
+                for arm in match_exp.arms {
+                    let matched = match arm.pattern {
+                        Some(pattern) => {
+                            Self::pattern_matches(&self.eval_expression(pattern)?, &subject)
+                        }
+                        None => true,
+                    };
+                    if matched {
+                        return self.eval_expression(arm.value);
+                    }
                 }
-                Self::eval_infix_expression(&inf_exp.operator, &left, &right)
+                Ok(NULL)
             }
-            ExpressionNode::IfExpressionNode(if_exp) => self.eval_if_expression(if_exp),
+            ExpressionNode::BlockExp(block) => self
+                .eval_scoped_block(block)
+                .and_then(EvalError::raise_signal),
             ExpressionNode::IdentifierNode(ident) => self.eval_identifier(ident),
-            ExpressionNode::Function(fn_lit) => Object::Func(Function {
+            ExpressionNode::Assign(assign) => {
+                let value = self.eval_expression(*assign.value)?;
+                self.env
+                    .borrow_mut()
+                    .assign(&assign.name.value, value.clone())
+                    .map_err(|msg| EvalError::at(msg, assign.name.token.position))?;
+                Ok(value)
+            }
+            ExpressionNode::Function(fn_lit) => Ok(Object::Func(Function {
                 parameters: fn_lit.parameters,
                 body: fn_lit.body,
                 env: self.env.clone(),
-            }),
+                variadic: fn_lit.variadic,
+            })),
+            ExpressionNode::Macro(macro_lit) => Ok(Object::Macro(Function {
+                parameters: macro_lit.parameters,
+                body: macro_lit.body,
+                env: self.env.clone(),
+                variadic: false,
+            })),
+            ExpressionNode::Call(call_exp) if is_call_to(&call_exp, "quote") => {
+                self.eval_quote(call_exp)
+            }
+            ExpressionNode::Call(call_exp) if is_call_to(&call_exp, "unquote") => {
+                Err(EvalError::at(
+                    String::from("unquote outside of quote"),
+                    call_exp.token.position,
+                ))
+            }
             ExpressionNode::Call(call_exp) => {
-                let function = self.eval_expression(call_exp.function.deref().clone());
-                if Self::is_error(&function) {
-                    return function;
-                }
-                let args = match self.eval_expressions(call_exp.arguments) {
-                    Ok(args) => args,
-                    Err(err) => return err,
-                };
+                let function = self.eval_expression(call_exp.function.deref().clone())?;
+                let args = self.eval_expressions(call_exp.arguments)?;
 
                 self.apply_function(function, args)
+                    .map_err(|err| err.or_at(call_exp.token.position))
             }
-            ExpressionNode::StringExp(string_literal) => Object::StringObj(string_literal.value),
+            ExpressionNode::StringExp(string_literal) => self.eval_string_literal(string_literal),
+            ExpressionNode::Char(char_literal) => Ok(Object::Char(char_literal.value)),
             ExpressionNode::Array(array_literal) => {
-                let elements = match self.eval_expressions(array_literal.elements) {
-                    Ok(elements) => elements,
-                    Err(err) => return err,
-                };
-                Object::Array(elements)
+                let elements = self.eval_expressions(array_literal.elements)?;
+                Ok(Object::Array(elements))
             }
             ExpressionNode::Index(index_exp) => {
-                let left = self.eval_expression(*index_exp.left);
-                if Self::is_error(&left) {
-                    return left;
-                }
-
-                let index = self.eval_expression(*index_exp.index);
-                if Self::is_error(&index) {
-                    return index;
-                }
+                let left = self.eval_expression(*index_exp.left)?;
+                let index = self.eval_expression(*index_exp.index)?;
 
                 self.eval_index_expression(left, index)
+                    .map_err(|msg| EvalError::at(msg, index_exp.token.position))
             }
             ExpressionNode::Hash(hash_literal) => {
-                let mut pairs = HashMap::new();
+                let mut pairs = HashStruct::new();
 
                 for (key_node, value_node) in hash_literal.pairs {
-                    let key = self.eval_expression(key_node);
-                    if Self::is_error(&key) {
-                        return key;
-                    }
-                    let value = self.eval_expression(value_node);
-                    if Self::is_error(&value) {
-                        return value;
-                    }
-                    let hash_key = match key.hash_key() {
-                        Ok(hash_key) => hash_key,
-                        Err(err) => return Object::Error(err),
-                    };
+                    let key = self.eval_expression(key_node)?;
+                    let value = self.eval_expression(value_node)?;
+                    let hash_key = key
+                        .hash_key()
+                        .map_err(|msg| EvalError::at(msg, hash_literal.token.position))?;
                     pairs.insert(hash_key, HashPair { key, value });
                 }
-                Object::HashObj(HashStruct { pairs })
+                Ok(Object::HashObj(pairs))
+            }
+            _ => Ok(NULL),
+        }
+    }
+
+    /// `quote(expr)` returns `expr` unevaluated, except that every
+    /// `unquote(x)` inside it is replaced by the literal for `x`'s value.
+    fn eval_quote(&mut self, call_exp: CallExpression) -> Result<Object, EvalError> {
+        let quoted = single_argument(call_exp)?;
+        let mut unquoter = Unquoter {
+            evaluator: self,
+            error: None,
+        };
+        let node = unquoter.rewrite_expression(quoted);
+
+        match unquoter.error {
+            Some(err) => Err(err),
+            None => Ok(Object::Quote(node)),
+        }
+    }
+
+    /// Expands `{expr}` segments by evaluating them in the current scope.
+    /// `{{` and `}}` produce literal braces. Raw strings are never expanded.
+    fn eval_string_literal(&mut self, literal: StringLiteral) -> Result<Object, EvalError> {
+        let value = literal.value;
+        if literal.raw || !value.contains(['{', '}']) {
+            return Ok(Object::StringObj(value));
+        }
+        // positions inside an interpolation are relative to the snippet, so
+        // every error is reported at the string literal itself
+        let at_literal = |err: EvalError| EvalError {
+            position: Some(literal.token.position),
+            ..err
+        };
+
+        let mut out = String::new();
+        let mut chars = value.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    out.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    out.push('}');
+                }
+                '{' => {
+                    let mut source = String::new();
+                    let mut depth = 1;
+                    loop {
+                        let inner = match chars.next() {
+                            Some(inner) => inner,
+                            None => {
+                                return Err(at_literal(EvalError::new(String::from(
+                                    "unterminated interpolation in string literal",
+                                ))))
+                            }
+                        };
+                        match inner {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        if depth == 0 {
+                            break;
+                        }
+                        source.push(inner);
+                    }
+                    let evaluated = self.eval_interpolation(&source).map_err(at_literal)?;
+                    out.push_str(evaluated.to_string().as_str());
+                }
+                _ => out.push(ch),
             }
-            _ => NULL,
+        }
+        Ok(Object::StringObj(out))
+    }
+
+    /// Evaluates the text between `{` and `}` as a single expression. Anything
+    /// that would bind, assign or jump is rejected, so an interpolation cannot
+    /// change the scope around the string.
+    fn eval_interpolation(&mut self, source: &str) -> Result<Object, EvalError> {
+        let lexer = Lexer::new(source);
+        let mut parser = Parser::new(lexer);
+        let mut program = parser.parse_program();
+
+        if !parser.errors().is_empty() {
+            return Err(EvalError::new(format!(
+                "invalid interpolation {{{}}}: {}",
+                source,
+                parser.errors().join(", ")
+            )));
+        }
+        if program.statements.is_empty() {
+            return Err(EvalError::new(String::from(
+                "empty interpolation in string literal",
+            )));
+        }
+
+        let invalid = |reason: &str| {
+            EvalError::new(format!("invalid interpolation {{{}}}: {}", source, reason))
+        };
+        if program.statements.len() > 1 {
+            return Err(invalid("expected a single expression"));
+        }
+        match program.statements.remove(0) {
+            StatementNode::Expression(stmt) => match stmt.expression {
+                ExpressionNode::Assign(_) => Err(invalid("assignment is not allowed")),
+                expression => self.eval_expression(expression),
+            },
+            // a leading `{` parses as a block statement; it is a block value here
+            StatementNode::Block(block) => self.eval_expression(ExpressionNode::BlockExp(block)),
+            stmt => Err(invalid(&format!(
+                "`{}` is not allowed",
+                stmt.token_literal()
+            ))),
         }
     }
 
-    fn eval_index_expression(&self, left: Object, index: Object) -> Object {
+    fn eval_index_expression(&self, left: Object, index: Object) -> Result<Object, String> {
         match (&left, &index) {
             (Object::Array(_), Object::Integer(_)) => {
-                Self::eval_array_index_expression(left, index)
+                Ok(Self::eval_array_index_expression(left, index))
             }
             (Object::HashObj(_), _) => Self::eval_hash_index_expression(left, index),
-            _ => Object::Error(format!(
+            _ => Err(format!(
                 "index operator not supported: {}",
                 left.object_type()
             )),
         }
     }
 
-    fn eval_hash_index_expression(hash: Object, index: Object) -> Object {
+    fn eval_hash_index_expression(hash: Object, index: Object) -> Result<Object, String> {
         match hash {
             Object::HashObj(hash_struct) => {
-                let key = match index.hash_key() {
-                    Ok(hash_key) => hash_key,
-                    Err(err) => return Object::Error(err),
-                };
+                let key = index.hash_key()?;
 
-                let pair = match hash_struct.pairs.get(&key) {
+                let pair = match hash_struct.get(&key) {
                     Some(hash_pair) => hash_pair,
-                    None => return NULL,
+                    None => return Ok(NULL),
                 };
 
-                pair.value.clone()
+                Ok(pair.value.clone())
             }
 
-            _ => Object::Error(format!(
+            _ => Err(format!(
                 "index operator not supported: {}",
                 hash.object_type()
             )),
@@ -185,11 +554,10 @@ impl Evaluator {
     fn eval_array_index_expression(array: Object, index: Object) -> Object {
         if let Object::Array(arr) = array {
             if let Object::Integer(idx) = index {
-                if arr.is_empty() {
-                    return NULL;
-                }
-                let max = (arr.len() - 1) as i64;
-                if idx < 0 || idx > max {
+                let len = arr.len() as i64;
+                // negative indices count back from the end, so `-1` is the last element
+                let idx = if idx < 0 { idx + len } else { idx };
+                if idx < 0 || idx >= len {
                     return NULL;
                 }
                 return arr[idx as usize].clone();
@@ -198,27 +566,79 @@ impl Evaluator {
         NULL
     }
 
-    fn apply_function(&mut self, func: Object, args: Vec<Object>) -> Object {
+    /// Calls a user-defined or builtin function with already-evaluated
+    /// arguments. Builtins such as `map` use this to invoke their callbacks.
+    pub fn apply_function(&mut self, func: Object, args: Vec<Object>) -> Result<Object, EvalError> {
         match func {
             Object::Func(function) => {
+                if function.variadic {
+                    let fixed = function.parameters.len() - 1;
+                    if args.len() < fixed {
+                        return Err(EvalError::new(format!(
+                            "wrong number of arguments. got={}, want=at least {}",
+                            args.len(),
+                            fixed
+                        )));
+                    }
+                } else if function.parameters.len() != args.len() {
+                    return Err(EvalError::new(format!(
+                        "wrong number of arguments. got={}, want={}",
+                        args.len(),
+                        function.parameters.len()
+                    )));
+                }
+
                 let old_env = self.env.clone();
                 let extended_env = self.extended_function_env(function.clone(), args);
 
+                // a loop around the call site is not one `break` in the body can end
+                let old_loop_depth = mem::replace(&mut self.loop_depth, 0);
                 self.env = extended_env;
                 let evaluated = self.eval_block_statement(function.body);
                 self.env = old_env;
-                Self::unwrap_return_value(evaluated)
+                self.loop_depth = old_loop_depth;
+                Ok(Self::unwrap_return_value(evaluated?))
             }
-            Object::Builtin(b_fn) => b_fn(args),
-            _ => Object::Error(format!("not a function: {}", func.object_type())),
+            Object::Builtin(b_fn) => b_fn(self, args),
+            _ => Err(EvalError::new(format!(
+                "not a function: {}",
+                func.object_type()
+            ))),
+        }
+    }
+
+    /// Calls `macro_fn` with `args` passed unevaluated as quotes, returning the
+    /// AST its body quoted.
+    pub(crate) fn apply_macro(
+        &mut self,
+        macro_fn: Function,
+        args: Vec<ExpressionNode>,
+    ) -> Result<ExpressionNode, EvalError> {
+        let args = args.into_iter().map(Object::Quote).collect();
+        match self.apply_function(Object::Func(macro_fn), args)? {
+            Object::Quote(node) => Ok(node),
+            other => Err(EvalError::new(format!(
+                "macro must return a quote, got {}",
+                other.object_type()
+            ))),
         }
     }
 
-    fn extended_function_env(&self, function: Function, args: Vec<Object>) -> Env {
+    fn extended_function_env(&self, function: Function, mut args: Vec<Object>) -> Env {
         let env = Environment::new_enclosed_environment(function.env);
+        let mut parameters = function.parameters;
+
+        if function.variadic {
+            // arity was checked by the caller, so the split point is in bounds
+            let rest_param = parameters
+                .pop()
+                .expect("variadic function has a rest parameter");
+            let rest = args.split_off(parameters.len());
+            env.borrow_mut().set(rest_param.value, Object::Array(rest));
+        }
 
-        for (idx, param) in function.parameters.into_iter().enumerate() {
-            env.borrow_mut().set(param.value, args[idx].clone());
+        for (param, arg) in parameters.into_iter().zip(args) {
+            env.borrow_mut().set(param.value, arg);
         }
         env
     }
@@ -233,15 +653,11 @@ impl Evaluator {
     fn eval_expressions(
         &mut self,
         expressions: Vec<ExpressionNode>,
-    ) -> Result<Vec<Object>, Object> {
+    ) -> Result<Vec<Object>, EvalError> {
         let mut result = Vec::new();
 
         for exp in expressions {
-            let evaluated = self.eval_expression(exp);
-            if Self::is_error(&evaluated) {
-                return Err(evaluated);
-            }
-            result.push(evaluated);
+            result.push(self.eval_expression(exp)?);
         }
         Ok(result)
     }
@@ -253,15 +669,12 @@ impl Evaluator {
         }
     }
 
-    fn is_error(obj: &Object) -> bool {
-        matches!(obj, Object::Error(_))
-    }
-
-    fn eval_prefix_expression(operator: &str, right: Object) -> Object {
+    fn eval_prefix_expression(operator: &str, right: Object) -> Result<Object, String> {
         match operator {
-            "!" => Self::eval_bang_operator_expression(right),
+            "!" => Ok(Self::eval_bang_operator_expression(right)),
             "-" => Self::eval_minus_prefix_operator_expression(right),
-            _ => Object::Error(format!(
+            "+" => Self::eval_plus_prefix_operator_expression(right),
+            _ => Err(format!(
                 "unknown operator: {} {}",
                 operator,
                 right.object_type()
@@ -269,60 +682,171 @@ impl Evaluator {
         }
     }
 
-    fn eval_infix_expression(operator: &str, left: &Object, right: &Object) -> Object {
+    /// The function a hash operand stores under the magic key for `operator`
+    /// (`"__add__"` for `+`), looking at the left operand first. When there is
+    /// one, it is called with both operands in place of the built-in operator.
+    fn operator_method(operator: &str, left: &Object, right: &Object) -> Option<Object> {
+        let key = match operator {
+            "+" => HashKey::String(String::from("__add__")),
+            _ => return None,
+        };
+        [left, right].into_iter().find_map(|operand| match operand {
+            Object::HashObj(hash) => match hash.get(&key) {
+                Some(HashPair {
+                    value: method @ (Object::Func(_) | Object::Builtin(_)),
+                    ..
+                }) => Some(method.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    /// Operands of different types are a "type mismatch"; operands of the same
+    /// type that don't support `operator` are an "unknown operator". The one
+    /// exception is numbers: an integer meeting a float is promoted to float.
+    /// Dividing a number by zero, or a float result that is not finite, is an
+    /// error rather than a panic or a NaN/infinity leaking into the program.
+    fn eval_infix_expression(
+        &self,
+        operator: &str,
+        left: &Object,
+        right: &Object,
+    ) -> Result<Object, String> {
+        let numbers = matches!(left, Object::Integer(_) | Object::Float(_))
+            && matches!(right, Object::Integer(_) | Object::Float(_));
+        let zero =
+            matches!(right, Object::Integer(0)) || matches!(right, Object::Float(r) if *r == 0.0);
+        if numbers && zero && (operator == "/" || operator == "%") {
+            return Err(String::from("division by zero"));
+        }
+
+        let floats = match (left, right) {
+            (Object::Float(l), Object::Float(r)) => Some((*l, *r)),
+            (Object::Integer(l), Object::Float(r)) => Some((*l as f64, *r)),
+            (Object::Float(l), Object::Integer(r)) => Some((*l, *r as f64)),
+            _ => None,
+        };
+        if let Some((l, r)) = floats {
+            return match Self::eval_float_infix_expression(operator, l, r, self.div_mode) {
+                Some(Object::Float(value)) if !value.is_finite() => {
+                    Err(format!("float overflow: {:?} {} {:?}", l, operator, r))
+                }
+                Some(result) => Ok(result),
+                // both operands are floats by now
+                None => Err(format!("unknown operator: FLOAT {} FLOAT", operator)),
+            };
+        }
+
         if left.object_type() != right.object_type() {
-            return Object::Error(format!(
+            return Err(format!(
                 "type mismatch: {} {} {}",
                 left.object_type(),
                 operator,
                 right.object_type()
             ));
         };
-        match (left, right, operator) {
+        let result = match (left, right, operator) {
             (Object::Integer(left_val), Object::Integer(right_val), op) => {
-                Self::eval_integer_infix_expression(op, *left_val, *right_val)
+                Self::eval_integer_infix_expression(op, *left_val, *right_val, self.div_mode)?
             }
             (Object::Boolean(left_val), Object::Boolean(right_val), op) => match op {
-                "==" => Self::native_bool_to_boolean_object(left_val == right_val),
-                "!=" => Self::native_bool_to_boolean_object(left_val != right_val),
-                _ => Object::Error(format!(
-                    "unknown operator: {} {} {}",
-                    left.object_type(),
-                    op,
-                    right.object_type()
-                )),
+                "==" => Some(Self::native_bool_to_boolean_object(left_val == right_val)),
+                "!=" => Some(Self::native_bool_to_boolean_object(left_val != right_val)),
+                _ => None,
+            },
+            (Object::Char(left_ch), Object::Char(right_ch), op) => match op {
+                "<" => Some(Self::native_bool_to_boolean_object(left_ch < right_ch)),
+                ">" => Some(Self::native_bool_to_boolean_object(left_ch > right_ch)),
+                "==" => Some(Self::native_bool_to_boolean_object(left_ch == right_ch)),
+                "!=" => Some(Self::native_bool_to_boolean_object(left_ch != right_ch)),
+                _ => None,
             },
             (Object::StringObj(left_str), Object::StringObj(right_str), op) => match op {
-                "+" => Object::StringObj(format!("{}{}", left_str, right_str)),
-                _ => Object::Error(format!(
-                    "unknown operator: {} {} {}",
-                    left.object_type(),
-                    op,
-                    right.object_type()
-                )),
+                "+" => Some(Object::StringObj(format!("{}{}", left_str, right_str))),
+                // lexicographic by Unicode scalar value
+                "<" => Some(Self::native_bool_to_boolean_object(left_str < right_str)),
+                ">" => Some(Self::native_bool_to_boolean_object(left_str > right_str)),
+                "==" => Some(Self::native_bool_to_boolean_object(left_str == right_str)),
+                "!=" => Some(Self::native_bool_to_boolean_object(left_str != right_str)),
+                _ => None,
             },
-            (left, right, op) => Object::Error(format!(
+            _ => None,
+        };
+        result.ok_or_else(|| {
+            format!(
                 "unknown operator: {} {} {}",
                 left.object_type(),
-                op,
+                operator,
                 right.object_type()
-            )),
+            )
+        })
+    }
+
+    /// Whether a `match` pattern equals the subject. Numbers compare the way
+    /// `==` compares them, so `2` matches `2.0`.
+    fn pattern_matches(pattern: &Object, subject: &Object) -> bool {
+        match (pattern, subject) {
+            (Object::Integer(p), Object::Float(s)) => *p as f64 == *s,
+            (Object::Float(p), Object::Integer(s)) => *p == *s as f64,
+            _ => pattern == subject,
         }
     }
 
-    fn eval_if_expression(&mut self, if_exp: IfExpression) -> Object {
-        let condition = self.eval_expression(*if_exp.condition);
+    fn eval_if_expression(&mut self, if_exp: IfExpression) -> Result<Object, EvalError> {
+        let condition = self.eval_expression(*if_exp.condition)?;
 
         if Self::is_truthy(condition) {
-            self.eval_block_statement(if_exp.consequence)
+            self.eval_scoped_block(if_exp.consequence)
         } else if let Some(alternative) = if_exp.alternative {
-            self.eval_block_statement(alternative)
+            self.eval_scoped_block(alternative)
         } else {
-            NULL
+            Ok(NULL)
+        }
+    }
+
+    fn eval_while_statement(&mut self, while_stmt: WhileStatement) -> Result<Object, EvalError> {
+        self.loop_depth += 1;
+        let result = self.run_while_loop(while_stmt);
+        self.loop_depth -= 1;
+        result
+    }
+
+    fn run_while_loop(&mut self, while_stmt: WhileStatement) -> Result<Object, EvalError> {
+        loop {
+            let condition = self.eval_expression(while_stmt.condition.clone())?;
+            if !Self::is_truthy(condition) {
+                return Ok(NULL);
+            }
+
+            match self.eval_scoped_block(while_stmt.body.clone())? {
+                Object::ReturnValue(ret) => return Ok(Object::ReturnValue(ret)),
+                Object::Break => return Ok(NULL),
+                _ => {}
+            }
+        }
+    }
+
+    /// Raises the `break` or `continue` signal, or fails when no loop in the
+    /// current function could catch it.
+    fn loop_signal(
+        &self,
+        signal: Object,
+        keyword: &str,
+        position: Position,
+    ) -> Result<Object, EvalError> {
+        if self.loop_depth == 0 {
+            return Err(EvalError::at(
+                format!("{} outside of loop", keyword),
+                position,
+            ));
         }
+        Ok(signal)
     }
 
-    fn is_truthy(obj: Object) -> bool {
+    /// Truthiness shared by `if` and predicate builtins: only `false` and
+    /// `null` are falsy.
+    pub(crate) fn is_truthy(obj: Object) -> bool {
         match obj {
             Object::Null => false,
             Object::Boolean(true) => true,
@@ -331,25 +855,39 @@ impl Evaluator {
         }
     }
 
-    fn eval_identifier(&self, identifier: Identifier) -> Object {
+    fn eval_identifier(&self, identifier: Identifier) -> Result<Object, EvalError> {
         let value = self.env.borrow().get(&identifier.value);
         match value {
-            Some(val) => val,
-            None => Object::Error(format!("identifier not found: {}", identifier.value)),
+            Some(val) => Ok(val),
+            None => Err(EvalError::at(
+                format!("identifier not found: {}", identifier.value),
+                identifier.token.position,
+            )),
         }
     }
 
-    fn eval_block_statement(&mut self, block: BlockStatement) -> Object {
+    /// Runs `block` in a fresh scope enclosing the current one, so its `let`s
+    /// shadow outer names only until the block ends. Each loop iteration gets
+    /// its own scope.
+    fn eval_scoped_block(&mut self, block: BlockStatement) -> Result<Object, EvalError> {
+        let outer = self.env.clone();
+        self.env = Environment::new_enclosed_environment(outer.clone());
+        let evaluated = self.eval_block_statement(block);
+        self.env = outer;
+        evaluated
+    }
+
+    fn eval_block_statement(&mut self, block: BlockStatement) -> Result<Object, EvalError> {
         let mut result = Object::Null;
 
         for stmt in block.statements {
-            result = self.eval_statement(stmt);
+            result = self.eval_statement(stmt)?;
 
-            if matches!(result, Object::ReturnValue(_) | Object::Error(_)) {
-                return result;
+            if let Object::ReturnValue(_) | Object::Break | Object::Continue = result {
+                return Ok(result);
             }
         }
-        result
+        Ok(result)
     }
 
     fn eval_bang_operator_expression(right: Object) -> Object {
@@ -361,25 +899,87 @@ impl Evaluator {
         }
     }
 
-    fn eval_minus_prefix_operator_expression(right: Object) -> Object {
+    fn eval_minus_prefix_operator_expression(right: Object) -> Result<Object, String> {
         match right {
-            Object::Integer(value) => Object::Integer(-value),
-            _ => Object::Error(format!("unknown operator: -{}", right.object_type())),
+            Object::Integer(value) => value
+                .checked_neg()
+                .map(Object::Integer)
+                .ok_or_else(|| format!("integer overflow: -({})", value)),
+            Object::Float(value) => Ok(Object::Float(-value)),
+            _ => Err(format!("unknown operator: -{}", right.object_type())),
         }
     }
 
-    fn eval_integer_infix_expression(operator: &str, left: i64, right: i64) -> Object {
-        match operator {
-            "+" => Object::Integer(left + right),
-            "-" => Object::Integer(left - right),
-            "*" => Object::Integer(left * right),
-            "/" => Object::Integer(left / right),
+    /// Unary `+` accepts only numbers and leaves them unchanged.
+    fn eval_plus_prefix_operator_expression(right: Object) -> Result<Object, String> {
+        match right {
+            Object::Integer(_) | Object::Float(_) => Ok(right),
+            _ => Err(format!("unknown operator: +{}", right.object_type())),
+        }
+    }
+
+    /// `Ok(None)` for an operator integers do not support. Arithmetic that
+    /// does not fit in an `i64` is an error; a zero divisor was already
+    /// rejected by the caller.
+    fn eval_integer_infix_expression(
+        operator: &str,
+        left: i64,
+        right: i64,
+        mode: DivMode,
+    ) -> Result<Option<Object>, String> {
+        // floor rounding only differs when the remainder's sign differs from
+        // the divisor's
+        let floors = mode == DivMode::Floor
+            && left.checked_rem(right).is_some_and(|rem| rem != 0)
+            && (left < 0) != (right < 0);
+        let value = match operator {
+            "+" => left.checked_add(right),
+            "-" => left.checked_sub(right),
+            "*" => left.checked_mul(right),
+            "/" if floors => left.checked_div(right).map(|quotient| quotient - 1),
+            "/" => left.checked_div(right),
+            "%" if floors => left.checked_rem(right).map(|rem| rem + right),
+            "%" => left.checked_rem(right),
+            "<" => return Ok(Some(Self::native_bool_to_boolean_object(left < right))),
+            ">" => return Ok(Some(Self::native_bool_to_boolean_object(left > right))),
+            "==" => return Ok(Some(Self::native_bool_to_boolean_object(left == right))),
+            "!=" => return Ok(Some(Self::native_bool_to_boolean_object(left != right))),
+            _ => return Ok(None),
+        };
+        match value {
+            Some(value) => Ok(Some(Object::Integer(value))),
+            None => Err(format!("integer overflow: {} {} {}", left, operator, right)),
+        }
+    }
+
+    /// `/` always divides exactly; `%` rounds the implied quotient the way
+    /// `mode` says, as it does for integers.
+    fn eval_float_infix_expression(
+        operator: &str,
+        left: f64,
+        right: f64,
+        mode: DivMode,
+    ) -> Option<Object> {
+        let result = match operator {
+            "+" => Object::Float(left + right),
+            "-" => Object::Float(left - right),
+            "*" => Object::Float(left * right),
+            "/" => Object::Float(left / right),
+            "%" => {
+                let rem = left % right;
+                if mode == DivMode::Floor && rem != 0.0 && (rem < 0.0) != (right < 0.0) {
+                    Object::Float(rem + right)
+                } else {
+                    Object::Float(rem)
+                }
+            }
             "<" => Self::native_bool_to_boolean_object(left < right),
             ">" => Self::native_bool_to_boolean_object(left > right),
             "==" => Self::native_bool_to_boolean_object(left == right),
             "!=" => Self::native_bool_to_boolean_object(left != right),
-            _ => NULL,
-        }
+            _ => return None,
+        };
+        Some(result)
     }
 }
 
@@ -389,6 +989,68 @@ impl Default for Evaluator {
     }
 }
 
+/// Names that are parsed as calls but evaluated specially: their arguments
+/// are not evaluated up front.
+pub(crate) const SPECIAL_FORMS: [&str; 2] = ["quote", "unquote"];
+
+pub(crate) fn is_call_to(call_exp: &CallExpression, name: &str) -> bool {
+    matches!(&*call_exp.function, ExpressionNode::IdentifierNode(ident) if &*ident.value == name)
+}
+
+fn single_argument(call_exp: CallExpression) -> Result<ExpressionNode, EvalError> {
+    let position = call_exp.token.position;
+    let mut arguments = call_exp.arguments;
+    if arguments.len() != 1 {
+        return Err(EvalError::at(
+            format!("wrong number of arguments. got={}, want=1", arguments.len()),
+            position,
+        ));
+    }
+    Ok(arguments.remove(0))
+}
+
+/// Splices values into a quoted expression, stopping at the first error.
+struct Unquoter<'e> {
+    evaluator: &'e mut Evaluator,
+    error: Option<EvalError>,
+}
+
+impl Unquoter<'_> {
+    fn unquote(&mut self, call_exp: CallExpression) -> Result<ExpressionNode, EvalError> {
+        let position = call_exp.token.position;
+        let value = self.evaluator.eval_expression(single_argument(call_exp)?)?;
+
+        match value {
+            Object::Integer(value) => Ok(integer_node(value, position)),
+            Object::Boolean(value) => Ok(boolean_node(value, position)),
+            Object::Quote(node) => Ok(node),
+            other => Err(EvalError::at(
+                format!("cannot unquote {}", other.object_type()),
+                position,
+            )),
+        }
+    }
+}
+
+impl Rewrite for Unquoter<'_> {
+    fn rewrite_expression(&mut self, expression: ExpressionNode) -> ExpressionNode {
+        match expression {
+            ExpressionNode::Call(call_exp)
+                if self.error.is_none() && is_call_to(&call_exp, "unquote") =>
+            {
+                match self.unquote(call_exp) {
+                    Ok(node) => node,
+                    Err(err) => {
+                        self.error = Some(err);
+                        ExpressionNode::None
+                    }
+                }
+            }
+            expression => walk_expression(self, expression),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::any;
@@ -397,9 +1059,10 @@ mod test {
         lexer::Lexer,
         object::{Hashable, Object, FALSE, NULL, TRUE},
         parser::Parser,
+        token::Position,
     };
 
-    use super::Evaluator;
+    use super::{DivMode, EvalError, Evaluator};
 
     #[test]
     fn test_eval_integer_expression() {
@@ -408,6 +1071,9 @@ mod test {
             ("10", 10),
             ("-5", -5),
             ("-10", -10),
+            ("+5", 5),
+            ("+-5", -5),
+            ("2 - +3", -1),
             ("5 + 5 + 5 + 5 - 10", 10),
             ("2 * 2 * 2 * 2 * 2", 32),
             ("-50 + 100 + -50", 0),
@@ -428,62 +1094,208 @@ mod test {
     }
 
     #[test]
-    fn test_eval_boolean_expression() {
+    fn test_eval_float_expression() {
         let tests = vec![
-            ("true", true),
-            ("false", false),
-            ("1 < 2", true),
-            ("1 > 2", false),
-            ("1 > 1", false),
-            ("1 == 1", true),
-            ("1 != 1", false),
-            ("1 == 2", false),
-            ("1 != 2", true),
-            ("true == true", true),
-            ("false == false", true),
-            ("true != false", true),
-            ("false != true", true),
-            ("(1 < 2) == true", true),
-            ("(1 < 2) == false", false),
-            ("(1 > 2) == true", false),
-            ("(1 > 2) == false", true),
+            ("2.5", "2.5"),
+            ("-1.5", "-1.5"),
+            ("1 + 0.5", "1.5"),
+            ("0.5 + 1", "1.5"),
+            ("1.5 * 2", "3.0"),
+            ("7.0 / 2", "3.5"),
+            ("7 / 2", "3"),
+            ("2.5 - 0.5", "2.0"),
+            ("1 < 1.5", "true"),
+            ("2.0 == 2", "true"),
+            ("2.5 != 2.5", "false"),
+            ("5.5 % 2", "1.5"),
+            ("-5.5 % 2", "-1.5"),
+            ("7 % 2.5", "2.0"),
+            ("5.5 % 0", "ERROR: division by zero"),
+            ("1.5 + true", "ERROR: type mismatch: FLOAT + BOOLEAN"),
         ];
 
-        for test in tests {
-            let evaluated = test_eval(test.0);
-            test_boolean_object(evaluated, test.1);
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
         }
     }
 
     #[test]
-    fn test_bang_operator() {
+    fn test_division_by_zero_and_float_overflow() {
         let tests = vec![
-            ("!true", false),
-            ("!false", true),
-            ("!5", false),
-            ("!!true", true),
-            ("!!false", false),
-            ("!!5", true),
+            ("1.0 / 0.0", "ERROR: division by zero"),
+            ("0.0 / 0.0", "ERROR: division by zero"),
+            ("1.5 / 0", "ERROR: division by zero"),
+            ("1 / 0", "ERROR: division by zero"),
+            ("1 / 0.0", "ERROR: division by zero"),
+            (
+                "let x = 1.0; while (true) { x = x * 2; }",
+                "ERROR: float overflow: 8.98846567431158e307 * 2.0",
+            ),
+            ("true / 0", "ERROR: type mismatch: BOOLEAN / INTEGER"),
+            ("0 / 1.0", "0.0"),
         ];
 
-        for test in tests {
-            let evaluated = test_eval(test.0);
-            test_boolean_object(evaluated, test.1);
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
         }
     }
 
     #[test]
-    fn test_id_else_expression() {
+    fn test_integer_overflow() {
+        let min = "(-9223372036854775807 - 1)";
         let tests = vec![
-            ("if (true) { 10 }", 10),
-            ("if (false) { 10 }", -999),
-            ("if (1) { 10 }", 10),
-            ("if (1 < 2) { 10 }", 10),
-            ("if (1 > 2) { 10 }", -999),
-            ("if (1 > 2) { 10 } else { 20 }", 20),
-            ("if (1 < 2) { 10 } else { 20 }", 10),
-        ];
-
+            (
+                format!("{} % -1", min),
+                "ERROR: integer overflow: -9223372036854775808 % -1",
+            ),
+            (
+                format!("{} / -1", min),
+                "ERROR: integer overflow: -9223372036854775808 / -1",
+            ),
+            (
+                format!("{} - 1", min),
+                "ERROR: integer overflow: -9223372036854775808 - 1",
+            ),
+            (
+                String::from("9223372036854775807 + 1"),
+                "ERROR: integer overflow: 9223372036854775807 + 1",
+            ),
+            (
+                String::from("let x = 4294967296; x * x"),
+                "ERROR: integer overflow: 4294967296 * 4294967296",
+            ),
+            (
+                format!("let m = {}; -m", min),
+                "ERROR: integer overflow: -(-9223372036854775808)",
+            ),
+            (format!("{} % 1", min), "0"),
+            (format!("{} + 1", min), "-9223372036854775807"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(&input).to_string(), expected, "input: {}", input);
+        }
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_div_mode(DivMode::Floor);
+        let program = Parser::new(Lexer::new("(-9223372036854775807 - 1) / -1")).parse_program();
+        assert_eq!(
+            evaluator.eval(program).map_err(|err| err.message),
+            Err(String::from("integer overflow: -9223372036854775808 / -1"))
+        );
+    }
+
+    #[test]
+    fn test_div_modes() {
+        let tests = vec![
+            ("-7 / 2", -3, -4),
+            ("-7 % 2", -1, 1),
+            ("7 / -2", -3, -4),
+            ("7 % -2", 1, -1),
+            ("7 / 2", 3, 3),
+            ("7 % 2", 1, 1),
+            ("-8 / 2", -4, -4),
+            ("-8 % 2", 0, 0),
+            ("(-7 / 2) * 2 + -7 % 2", -7, -7),
+        ];
+
+        for (input, trunc, floor) in tests {
+            for (mode, expected) in [(DivMode::Trunc, trunc), (DivMode::Floor, floor)] {
+                let mut evaluator = Evaluator::new();
+                evaluator.set_div_mode(mode);
+                let program = Parser::new(Lexer::new(input)).parse_program();
+                assert_eq!(
+                    evaluator.eval_program(program),
+                    Object::Integer(expected),
+                    "input: {} in {:?} mode",
+                    input,
+                    mode
+                );
+            }
+        }
+
+        assert_eq!(test_eval("5 % 0").to_string(), "ERROR: division by zero");
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_div_mode(DivMode::Floor);
+        let program = Parser::new(Lexer::new("-5.5 % 2")).parse_program();
+        assert_eq!(evaluator.eval_program(program), Object::Float(0.5));
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let tests = vec![
+            ("'a'", "'a'"),
+            ("'\\n'", "'\\n'"),
+            ("'a' == 'a'", "true"),
+            ("'a' < 'b'", "true"),
+            ("'a' == \"a\"", "ERROR: type mismatch: CHAR == STRING"),
+            ("[' ', 'é']", "[' ', 'é']"),
+            ("'a' + 'b'", "ERROR: unknown operator: CHAR + CHAR"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_eval_boolean_expression() {
+        let tests = vec![
+            ("true", true),
+            ("false", false),
+            ("1 < 2", true),
+            ("1 > 2", false),
+            ("1 > 1", false),
+            ("1 == 1", true),
+            ("1 != 1", false),
+            ("1 == 2", false),
+            ("1 != 2", true),
+            ("true == true", true),
+            ("false == false", true),
+            ("true != false", true),
+            ("false != true", true),
+            ("(1 < 2) == true", true),
+            ("(1 < 2) == false", false),
+            ("(1 > 2) == true", false),
+            ("(1 > 2) == false", true),
+        ];
+
+        for test in tests {
+            let evaluated = test_eval(test.0);
+            test_boolean_object(evaluated, test.1);
+        }
+    }
+
+    #[test]
+    fn test_bang_operator() {
+        let tests = vec![
+            ("!true", false),
+            ("!false", true),
+            ("!5", false),
+            ("!!true", true),
+            ("!!false", false),
+            ("!!5", true),
+        ];
+
+        for test in tests {
+            let evaluated = test_eval(test.0);
+            test_boolean_object(evaluated, test.1);
+        }
+    }
+
+    #[test]
+    fn test_id_else_expression() {
+        let tests = vec![
+            ("if (true) { 10 }", 10),
+            ("if (false) { 10 }", -999),
+            ("if (1) { 10 }", 10),
+            ("if (1 < 2) { 10 }", 10),
+            ("if (1 > 2) { 10 }", -999),
+            ("if (1 > 2) { 10 } else { 20 }", 20),
+            ("if (1 < 2) { 10 } else { 20 }", 10),
+        ];
+
         for test in tests {
             let evaluated = test_eval(test.0);
             if test.1 == -999 {
@@ -510,12 +1322,171 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_semicolon_free_programs() {
+        let tests = vec![
+            ("5\n10\n15", 15),
+            ("let x = 5\nlet y = x * 2\ny", 10),
+            ("let f = fn(x) {\n  let y = x + 1\n  y * 2\n}\nf(3)", 8),
+            ("if (true) { 1\n2 } else { 3 }", 2),
+        ];
+
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_program_value_is_last_statement() {
+        test_integer_object(test_eval("let x = 5; x + 1"), 6);
+        test_integer_object(test_eval("let x = 5; let y = x * 2;"), 10);
+        test_integer_object(test_eval("1; return 2; 3"), 2);
+        test_null_object(test_eval("while (false) {}"));
+    }
+
+    #[test]
+    fn test_empty_input() {
+        for input in ["", "   ", "\n\n", "\t /* nothing */ \n"] {
+            test_null_object(test_eval(input));
+        }
+    }
+
+    #[test]
+    fn test_block_expressions() {
+        let tests = vec![
+            ("let y = { let x = 5; x + 1 }; y", 6),
+            ("let x = 1; let y = { let x = 10; x * 2 }; x + y", 21),
+            ("{ 1; 2 }", 2),
+            ("let f = fn() { let v = { return 5; }; 10 }; f()", 5),
+            (r#"let h = {"a": 1}; h["a"]"#, 1),
+            ("let x = { fn g() { 2 } g() }; x", 2),
+            ("{ fn f() { 1 } f() }", 1),
+        ];
+
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+
+        test_integer_object(test_eval("{ let x = 1; x }"), 1);
+        test_integer_object(test_eval("let x = 5; { let x = 1; x } x"), 5);
+        test_integer_object(test_eval("let f = fn() { { return 7; } 0 }; f()"), 7);
+
+        match test_eval("{ let z = 3; z }; z") {
+            Object::Error(err) => assert_eq!(err, "identifier not found: z"),
+            other => panic!("object is not Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_assignment_and_constants() {
+        let tests = vec![
+            ("let x = 1; x = 2; x", 2),
+            ("let x = 1; x = x + 41", 42),
+            ("let a = 0; let b = 0; a = b = 7; a + b", 14),
+            (
+                "let n = 0; let bump = fn() { n = n + 1 }; bump(); bump(); n",
+                2,
+            ),
+            ("const PI = 3; PI * 2", 6),
+            ("const PI = 3; let f = fn() { let PI = 4; PI }; f() + PI", 7),
+        ];
+
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+
+        let errors = vec![
+            ("const PI = 3; PI = 4;", "cannot assign to constant 'PI'"),
+            (
+                "const PI = 3; let PI = 4;",
+                "cannot assign to constant 'PI'",
+            ),
+            (
+                "const PI = 3; let f = fn() { PI = 4 }; f()",
+                "cannot assign to constant 'PI'",
+            ),
+            ("missing = 1", "identifier not found: missing"),
+        ];
+
+        for (input, expected) in errors {
+            match test_eval(input) {
+                Object::Error(err) => assert_eq!(err, expected, "input: {}", input),
+                other => panic!("object is not Error, got {:?} for {}", other, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote() {
+        let tests = vec![
+            ("quote(5)", "5"),
+            ("quote(5 + 5)", "(5 + 5)"),
+            ("quote(foobar)", "foobar"),
+            ("quote(foobar + barfoo)", "(foobar + barfoo)"),
+        ];
+
+        for (input, expected) in tests {
+            match test_eval(input) {
+                Object::Quote(node) => assert_eq!(node.to_string(), expected, "input: {}", input),
+                other => panic!("object is not Quote, got {:?} for {}", other, input),
+            }
+        }
+        assert_eq!(test_eval("quote(5 + 5)").to_string(), "QUOTE((5 + 5))");
+    }
+
+    #[test]
+    fn test_quote_unquote() {
+        let tests = vec![
+            ("quote(unquote(1 + 2))", "3"),
+            ("quote(unquote(4))", "4"),
+            ("quote(8 + unquote(4 + 4))", "(8 + 8)"),
+            ("quote(unquote(4 + 4) + 8)", "(8 + 8)"),
+            ("let foobar = 8; quote(foobar)", "foobar"),
+            ("let foobar = 8; quote(unquote(foobar))", "8"),
+            ("quote(unquote(true))", "true"),
+            ("quote(unquote(true == false))", "false"),
+            ("quote(unquote(quote(4 + 4)))", "(4 + 4)"),
+            (
+                "let quotedInfix = quote(4 + 4); quote(unquote(4 + 4) + unquote(quotedInfix))",
+                "(8 + (4 + 4))",
+            ),
+            ("quote(fn(x) { unquote(1 + 1) * x })", "fn(x) (2 * x)"),
+        ];
+
+        for (input, expected) in tests {
+            match test_eval(input) {
+                Object::Quote(node) => assert_eq!(node.to_string(), expected, "input: {}", input),
+                other => panic!("object is not Quote, got {:?} for {}", other, input),
+            }
+        }
+
+        let errors = vec![
+            ("quote(1, 2)", "wrong number of arguments. got=2, want=1"),
+            (
+                "quote(unquote())",
+                "wrong number of arguments. got=0, want=1",
+            ),
+            (r#"quote(unquote("a"))"#, "cannot unquote STRING"),
+            ("quote(unquote(missing))", "identifier not found: missing"),
+            ("unquote(1)", "unquote outside of quote"),
+        ];
+
+        for (input, expected) in errors {
+            match test_eval(input) {
+                Object::Error(err) => assert_eq!(err, expected, "input: {}", input),
+                other => panic!("object is not Error, got {:?} for {}", other, input),
+            }
+        }
+    }
+
     #[test]
     fn test_error_handling() {
         let tests = vec![
             ("5 + true;", "type mismatch: INTEGER + BOOLEAN"),
             ("5 + true; 5;", "type mismatch: INTEGER + BOOLEAN"),
             ("-true", "unknown operator: -BOOLEAN"),
+            ("+true", "unknown operator: +BOOLEAN"),
+            (r#"+"five""#, "unknown operator: +STRING"),
             ("true + false;", "unknown operator: BOOLEAN + BOOLEAN"),
             ("5; true + false; 5", "unknown operator: BOOLEAN + BOOLEAN"),
             (
@@ -533,6 +1504,16 @@ mod test {
             ),
             ("foobar", "identifier not found: foobar"),
             (r#""Hello" - "World""#, "unknown operator: STRING - STRING"),
+            (r#""a" * 2"#, "type mismatch: STRING * INTEGER"),
+            ("true < false", "unknown operator: BOOLEAN < BOOLEAN"),
+            ("[1] + [2]", "unknown operator: ARRAY + ARRAY"),
+            ("[1] == 1", "type mismatch: ARRAY == INTEGER"),
+            (r#"{} + {}"#, "unknown operator: HASH + HASH"),
+            (
+                "fn() {} == fn() {}",
+                "unknown operator: FUNCTION == FUNCTION",
+            ),
+            ("len == len", "unknown operator: BUILTIN == BUILTIN"),
             (
                 r#"{"name": "Monkey"}[fn(x) { x }];"#,
                 "unusable as hash key: FUNCTION",
@@ -548,6 +1529,101 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_eval_error_keeps_position() {
+        let input = "let f = fn() {\n  missing\n};\nmap([1], fn(x) { f() });";
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        let mut evaluator = Evaluator::new();
+        assert_eq!(
+            evaluator.eval(program),
+            Err(EvalError::at(
+                String::from("identifier not found: missing"),
+                Position { line: 2, column: 3 },
+            ))
+        );
+    }
+
+    #[test]
+    fn test_let_without_value() {
+        test_null_object(test_eval("let x; x"));
+        test_integer_object(test_eval("let x; x = 5; x"), 5);
+        test_integer_object(
+            test_eval("let max; if (3 > 2) { max = 3 } else { max = 2 }; max"),
+            3,
+        );
+    }
+
+    #[test]
+    fn test_destructuring_let() {
+        let tests = vec![
+            ("let [a, b] = [1, 2]; a * 10 + b", "12"),
+            ("let [x] = [[1, 2]]; x", "[1, 2]"),
+            ("let [head, ...tail] = [1, 2, 3]; tail", "[2, 3]"),
+            ("let [head, ...tail] = [1]; tail", "[]"),
+            ("let [...all] = []; all", "[]"),
+            ("let [a, b] = [1, 2]", "[1, 2]"),
+            (
+                "let [a, b] = [1, 2, 3];",
+                "ERROR: [line 1, col 1] cannot destructure an array of 3 elements into 2 names",
+            ),
+            (
+                "let [a, b, ...c] = [1];",
+                "ERROR: [line 1, col 1] cannot destructure an array of 1 elements into at least 2 names",
+            ),
+            (
+                "let [a] = 5;",
+                "ERROR: [line 1, col 1] cannot destructure INTEGER, expected ARRAY",
+            ),
+            (
+                "const [a] = [1]; let [a] = [2];",
+                "ERROR: [line 1, col 18] cannot assign to constant 'a'",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            let evaluated = Evaluator::new().eval_program(program);
+            assert_eq!(evaluated.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_error_messages_include_position() {
+        let tests = vec![
+            (
+                "let a = 1;\nlet b = 2;\n   x;",
+                "[line 3, col 4] identifier not found: x",
+            ),
+            (
+                "5 + true;",
+                "[line 1, col 3] type mismatch: INTEGER + BOOLEAN",
+            ),
+            (
+                "let s = \"a\";\n-s",
+                "[line 2, col 1] unknown operator: -STRING",
+            ),
+            (
+                "len(1)",
+                "[line 1, col 4] argument to `len` not supported, got INTEGER",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            let mut evaluator = Evaluator::new();
+            match evaluator.eval_program(program) {
+                Object::Error(err) => assert_eq!(err, expected, "input: {}", input),
+                other => panic!("expected error object, got {:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn test_let_statements() {
         let tests = vec![
@@ -578,13 +1654,13 @@ mod test {
                     func.parameters[0].to_string(),
                     "x",
                     "paramentes is not 'x', got={}",
-                    func.parameters[0].to_string()
+                    func.parameters[0]
                 );
                 assert_eq!(
                     func.body.to_string(),
                     "(x + 2)",
                     "function body is not '(x + 2);', got={}",
-                    func.body.to_string()
+                    func.body
                 );
             }
             _ => panic!("object is not Function, got {:?}", evaluated),
@@ -607,6 +1683,248 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_argument_count_mismatch() {
+        let add = "let add = fn(x, y) { x + y };";
+        let tests = vec![
+            ("add(1);", Err("wrong number of arguments. got=1, want=2")),
+            (
+                "add(1, 2, 3);",
+                Err("wrong number of arguments. got=3, want=2"),
+            ),
+            ("add();", Err("wrong number of arguments. got=0, want=2")),
+            ("add(1, 2);", Ok(3)),
+        ];
+
+        for (call, expected) in tests {
+            let input = format!("{} {}", add, call);
+            match (test_eval(&input), expected) {
+                (Object::Error(err), Err(expected)) => {
+                    assert_eq!(err, expected, "input: {}", input)
+                }
+                (obj, Ok(expected)) => test_integer_object(obj, expected),
+                (other, Err(expected)) => {
+                    panic!(
+                        "expected error {:?}, got {:?} for {}",
+                        expected, other, input
+                    )
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_variadic_functions() {
+        let input = "let f = fn(first, ...rest) { rest }; f(1, 2, 3);";
+        match test_eval(input) {
+            Object::Array(elements) => {
+                assert_eq!(elements.len(), 2, "rest has wrong length");
+                test_integer_object(elements[0].clone(), 2);
+                test_integer_object(elements[1].clone(), 3);
+            }
+            other => panic!("object is not Array, got {:?}", other),
+        }
+
+        let input = "let f = fn(first, ...rest) { len(rest) + first }; f(10);";
+        test_integer_object(test_eval(input), 10);
+
+        let input = "let f = fn(a, b, ...rest) { rest }; f(1);";
+        match test_eval(input) {
+            Object::Error(err) => {
+                assert_eq!(err, "wrong number of arguments. got=1, want=at least 2")
+            }
+            other => panic!("Expected error object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_while_loops() {
+        let tests = vec![
+            (
+                "let i = 0; let sum = 0; while (i < 5) { sum = sum + i; i = i + 1; } sum;",
+                10,
+            ),
+            ("let i = 10; while (i < 5) { i = i + 1; } i;", 10),
+            (
+                "let f = fn() { let i = 0; while (true) { if (i > 2) { return i; } i = i + 1; } }; f();",
+                3,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let tests = vec![
+            (
+                "let i = 0; while (true) { if (i == 3) { break; } i = i + 1; } i",
+                "3",
+            ),
+            (
+                "let i = 0; let sum = 0;
+                while (i < 6) { i = i + 1; if (i == 2) { continue; } sum = sum + i; }
+                sum",
+                "19",
+            ),
+            ("while (true) { break; }", "null"),
+            (
+                "let i = 0; let steps = [0];
+                while (i < 2) {
+                    i = i + 1;
+                    let j = 0;
+                    while (true) { j = j + 1; if (j > i) { break } }
+                    steps = push(steps, j);
+                }
+                steps",
+                "[0, 2, 3]",
+            ),
+            (
+                "let f = fn() { let i = 0; while (true) { i = i + 1; { if (i > 4) { return i; } } } }; f()",
+                "5",
+            ),
+            ("let i = 0; while (i < 3) { i = i + 1; continue; i = 100; } i", "3"),
+            // signals from a block used as an operand still reach their loop
+            (
+                "let i = 0; while (i < 5) { i = i + 1; let a = [{ break; }]; } i",
+                "1",
+            ),
+            (
+                "let i = 0; let n = 0;
+                while (i < 5) { i = i + 1; let a = [if (i % 2 == 0) { continue; } else { i }]; n = n + 1; }
+                n",
+                "3",
+            ),
+            ("fn() { let a = [{ return 5; }]; 10 }()", "5"),
+            ("fn() { 1 + { return 5; } }()", "5"),
+            ("fn() { let a = if (true) { return 5; }; 10 }()", "5"),
+            ("fn() { let h = {\"k\": { return 5; }}; 10 }()", "5"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_break_outside_of_loop() {
+        let tests = vec![
+            ("break;", "[line 1, col 1] break outside of loop"),
+            (
+                "if (true) { continue }",
+                "[line 1, col 13] continue outside of loop",
+            ),
+            (
+                "let f = fn() { break; }; while (true) { f(); }",
+                "[line 1, col 16] break outside of loop",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            match Evaluator::new().eval(program) {
+                Err(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+                Ok(obj) => panic!("expected an error, got {:?} for {}", obj, input),
+            }
+        }
+    }
+
+    #[test]
+    fn test_match_expressions() {
+        let describe = r#"let describe = fn(x) {
+            match (x) { 1 => "one", 2 => "two", "hi" => "greeting", [1] => "list", _ => "other" }
+        };"#;
+        let tests = vec![
+            ("describe(1)", "one"),
+            ("describe(2)", "two"),
+            (r#"describe("hi")"#, "greeting"),
+            ("describe([1])", "list"),
+            ("describe(3)", "other"),
+            ("describe(true)", "other"),
+        ];
+
+        for (input, expected) in tests {
+            let program = format!("{} {}", describe, input);
+            assert_eq!(
+                test_eval(&program).to_string(),
+                expected,
+                "input: {}",
+                input
+            );
+        }
+
+        let tests = vec![
+            ("match (5) { 1 => 10 }", "null"),
+            (
+                "let x = 4; match (x * 2) { x + 4 => \"sum\", _ => \"none\" }",
+                "sum",
+            ),
+            ("match (1) { _ => 1, 1 => 2 }", "1"),
+            ("match (2) { 1 => missing, 2 => 20 }", "20"),
+            ("match (1) { 1 => { let y = 5; y * 2 } }", "10"),
+            ("match (2) { 2.0 => 1, _ => 0 }", "1"),
+            ("match (2.0) { 1 => 0, 2 => 1 }", "1"),
+            ("match (2.5) { 2 => 0, _ => 1 }", "1"),
+        ];
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_block_scoping() {
+        let tests = vec![
+            ("let x = 1; if (true) { let x = 2; } x", 1),
+            ("let x = 1; if (false) { 0 } else { let x = 2; } x", 1),
+            ("let x = 1; if (true) { let x = x + 1; x }", 2),
+            ("let x = 1; if (true) { x = 5; } x", 5),
+            (
+                "let x = 1; let i = 0; while (i < 3) { let x = i * 10; i = i + 1; } x",
+                1,
+            ),
+            (
+                "let total = 0; let i = 0; while (i < 3) { let step = i + 1; total = total + step; i = i + 1; } total",
+                6,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            test_integer_object(test_eval(input), expected);
+        }
+
+        match test_eval("if (true) { let inner = 1; } inner") {
+            Object::Error(err) => assert_eq!(err, "identifier not found: inner"),
+            other => panic!("object is not Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_execution_budget() {
+        let program = Parser::new(Lexer::new("while (true) {}")).parse_program();
+
+        let mut evaluator = Evaluator::new();
+        evaluator.set_budget(Some(100));
+        match evaluator.eval(program) {
+            Err(err) => assert_eq!(err.message, "execution budget exceeded"),
+            Ok(obj) => panic!("expected budget error, got {:?}", obj),
+        }
+        assert_eq!(evaluator.steps(), 101);
+
+        evaluator.reset_steps();
+        let program = Parser::new(Lexer::new("1 + 2")).parse_program();
+        assert_eq!(evaluator.eval(program), Ok(Object::Integer(3)));
+
+        // builtins charge for the work they do, not just for the call
+        evaluator.reset_steps();
+        let program = Parser::new(Lexer::new("len(range(1000000))")).parse_program();
+        match evaluator.eval(program) {
+            Err(err) => assert_eq!(err.message, "execution budget exceeded"),
+            Ok(obj) => panic!("expected budget error, got {:?}", obj),
+        }
+    }
+
     #[test]
     fn test_closures() {
         let input = r#"
@@ -634,6 +1952,61 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_method_call_syntax() {
+        let tests = vec![
+            (r#""hello".len()"#, "5"),
+            ("[1, 2, 3].first()", "1"),
+            ("[1, 2].push(3).len()", "3"),
+            ("let double = fn(x) { x * 2 }; 21.double()", "42"),
+            (
+                "[1, 2, 3, 4].filter(fn(x) { x > 1 }).map(fn(x) { x * 10 })",
+                "[20, 30, 40]",
+            ),
+            ("5.missing()", "ERROR: identifier not found: missing"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_arrow_functions() {
+        let tests = vec![
+            ("let inc = x -> x + 1; inc(41)", "42"),
+            ("let add = (a, b) -> a + b; add(2, 3)", "5"),
+            ("(() -> 7)()", "7"),
+            ("map([1, 2, 3], x -> x * x)", "[1, 4, 9]"),
+            ("let adder = x -> y -> x + y; adder(1)(2)", "3"),
+            (
+                "let inc = x -> x + 1; inc(1, 2)",
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_pipe_operator() {
+        let tests = vec![
+            (
+                "let double = fn(x) { x * 2 }; let inc = fn(x) { x + 1 }; 5 |> double |> inc",
+                "11",
+            ),
+            ("[1, 2, 3] |> len", "3"),
+            ("4 |> x -> x * x", "16"),
+            ("5 |> 3", "ERROR: not a function: INTEGER"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_string_concatenation() {
         let input = r#""Hello" + " " + "World!""#;
@@ -646,6 +2019,111 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_string_comparison() {
+        let tests = vec![
+            (r#""apple" < "banana""#, true),
+            (r#""banana" < "apple""#, false),
+            (r#""apple" > "Apple""#, true),
+            (r#""app" < "apple""#, true),
+            (r#""apple" == "apple""#, true),
+            (r#""apple" == "pear""#, false),
+            (r#""apple" != "pear""#, true),
+            (r#""apple" != "apple""#, false),
+        ];
+
+        for (input, expected) in tests {
+            test_boolean_object(test_eval(input), expected);
+        }
+
+        match test_eval(r#""1" < 2"#) {
+            Object::Error(err) => assert_eq!(err, "type mismatch: STRING < INTEGER"),
+            other => panic!("object is not Error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_string_interpolation() {
+        let tests = vec![
+            (r#"let x = 5; "x is {x}""#, Ok("x is 5")),
+            (
+                r#"let a = 2; let b = 3; "{a} + {b} = {a + b}""#,
+                Ok("2 + 3 = 5"),
+            ),
+            (r#""{{x}} is literal""#, Ok("{x} is literal")),
+            (r#""\x7B1}""#, Ok("{1}")),
+            (r#""a\u{7B}1+1}""#, Ok("a{1+1}")),
+            (r#""\x7B{1}\x7D""#, Ok("{1}")),
+            (
+                r#""bad {1 +}""#,
+                Err("invalid interpolation {1 +}: no prefix parse function for 'Eof' found"),
+            ),
+            (r#"let h = {1: [1, 2]}; "got {h[1]}""#, Ok("got [1, 2]")),
+            (r#""{missing}""#, Err("identifier not found: missing")),
+            (
+                r#""{let y = 3}""#,
+                Err("invalid interpolation {let y = 3}: `let` is not allowed"),
+            ),
+            (
+                r#""{const y = 3}""#,
+                Err("invalid interpolation {const y = 3}: `const` is not allowed"),
+            ),
+            (
+                r#"let x = 1; "{x = 9}""#,
+                Err("invalid interpolation {x = 9}: assignment is not allowed"),
+            ),
+            (
+                r#"fn() { "{return 1}"; 2 }()"#,
+                Err("invalid interpolation {return 1}: `return` is not allowed"),
+            ),
+            (
+                r#"while (true) { "{break}" }"#,
+                Err("invalid interpolation {break}: `break` is not allowed"),
+            ),
+            (
+                r#"while (true) { "{continue}" }"#,
+                Err("invalid interpolation {continue}: `continue` is not allowed"),
+            ),
+            (
+                r#""{1; 2}""#,
+                Err("invalid interpolation {1; 2}: expected a single expression"),
+            ),
+            (r#"let x = 1; "{x == 1}""#, Ok("true")),
+            (r#""{ {let y = 3; y * 2} }""#, Ok("6")),
+            (r#"let y = 1; "{ {let y = 3; y} }" + "{y}""#, Ok("31")),
+            (
+                r#""open {x""#,
+                Err("unterminated interpolation in string literal"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            match (test_eval(input), expected) {
+                (Object::StringObj(str), Ok(expected)) => assert_eq!(str, expected),
+                (Object::Error(err), Err(expected)) => assert_eq!(err, expected),
+                (other, expected) => panic!("expected {:?}, got {:?}", expected, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_strings_are_not_interpolated() {
+        let tests = vec![
+            (r"`\d{3}`", r"\d{3}"),
+            ("`a{1}b`", "a{1}b"),
+            ("`{x}`", "{x}"),
+            ("`{{`", "{{"),
+            ("let x = 1; `{x}` + \"{x}\"", "{x}1"),
+        ];
+
+        for (input, expected) in tests {
+            match test_eval(input) {
+                Object::StringObj(str) => assert_eq!(str, expected, "input: {}", input),
+                other => panic!("expected a string for {}, got {:?}", input, other),
+            }
+        }
+    }
+
     #[test]
     fn test_builtin_functions() {
         let tests: Vec<(&str, Box<dyn any::Any>)> = vec![
@@ -668,6 +2146,103 @@ mod test {
                 r#"push([1, 2, 3], 4)"#,
                 Box::new(vec![1_i64, 2_i64, 3_i64, 4_i64]),
             ),
+            (
+                r#"map([1, 2, 3], fn(x) { x * 2 })"#,
+                Box::new(vec![2_i64, 4_i64, 6_i64]),
+            ),
+            (r#"map([], fn(x) { x * 2 })"#, Box::new(Vec::<i64>::new())),
+            (
+                r#"map(1, fn(x) { x * 2 })"#,
+                Box::new(String::from("argument to `map` not supported, got INTEGER")),
+            ),
+            (
+                r#"map([1, 2], 3)"#,
+                Box::new(String::from(
+                    "second argument to `map` must be FUNCTION, got INTEGER",
+                )),
+            ),
+            (
+                r#"map([1, 2], fn(x, y) { x + y })"#,
+                Box::new(String::from("wrong number of arguments. got=1, want=2")),
+            ),
+            (
+                r#"map([1, true], fn(x) { -x })"#,
+                Box::new(String::from("unknown operator: -BOOLEAN")),
+            ),
+            (
+                r#"reduce([1, 2, 3, 4], 0, fn(acc, x) { acc + x })"#,
+                Box::new(10_i64),
+            ),
+            (r#"reduce([], 7, fn(acc, x) { acc + x })"#, Box::new(7_i64)),
+            (
+                r#"reduce([1, 2], 0, 5)"#,
+                Box::new(String::from(
+                    "third argument to `reduce` must be FUNCTION, got INTEGER",
+                )),
+            ),
+            (
+                r#"reduce([1, true], 0, fn(acc, x) { acc + x })"#,
+                Box::new(String::from("type mismatch: INTEGER + BOOLEAN")),
+            ),
+            (
+                r#"filter([1, 2, 3, 4], fn(x) { x > 2 })"#,
+                Box::new(vec![3_i64, 4_i64]),
+            ),
+            (
+                r#"filter([1, 2, 3], fn(x) { x > 5 })"#,
+                Box::new(Vec::<i64>::new()),
+            ),
+            (
+                r#"filter([1, 2, 3], fn(x) { if (x == 2) { 0 } })"#,
+                Box::new(vec![2_i64]),
+            ),
+            (
+                r#"filter([1, 2], "nope")"#,
+                Box::new(String::from(
+                    "second argument to `filter` must be FUNCTION, got STRING",
+                )),
+            ),
+            (r#"range(5)"#, Box::new(vec![0_i64, 1, 2, 3, 4])),
+            (r#"range(2, 5)"#, Box::new(vec![2_i64, 3, 4])),
+            (r#"range(-2, 1)"#, Box::new(vec![-2_i64, -1, 0])),
+            (r#"range(5, 2)"#, Box::new(Vec::<i64>::new())),
+            (r#"range(0)"#, Box::new(Vec::<i64>::new())),
+            (
+                r#"range(9223372036854775807)"#,
+                Box::new(String::from(
+                    "range too large: 9223372036854775807 elements",
+                )),
+            ),
+            (
+                r#"range(-9223372036854775807, 9223372036854775807)"#,
+                Box::new(String::from(
+                    "range too large: 18446744073709551614 elements",
+                )),
+            ),
+            (
+                r#"range("5")"#,
+                Box::new(String::from(
+                    "arguments to `range` must be INTEGER, got STRING",
+                )),
+            ),
+            (
+                r#"range(1, true)"#,
+                Box::new(String::from(
+                    "arguments to `range` must be INTEGER, got INTEGER and BOOLEAN",
+                )),
+            ),
+            (
+                r#"range()"#,
+                Box::new(String::from(
+                    "wrong number of arguments. got=0, want=1 or 2",
+                )),
+            ),
+            (
+                r#"filter("abc", fn(x) { true })"#,
+                Box::new(String::from(
+                    "argument to `filter` not supported, got STRING",
+                )),
+            ),
         ];
 
         for test in tests {
@@ -702,6 +2277,47 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_reduce_builds_string() {
+        let input = r#"reduce(["a", "b", "c"], ">", fn(acc, x) { acc + x })"#;
+        match test_eval(input) {
+            Object::StringObj(str) => {
+                assert_eq!(str, ">abc", "String has wrong value, got={}", str)
+            }
+            other => panic!("object is not String, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_contains_builtin() {
+        let tests = vec![
+            (r#"contains([1, 2, 3], 2)"#, Ok(true)),
+            (r#"contains([1, 2, 3], 4)"#, Ok(false)),
+            (r#"contains([1, "two", [3]], [3])"#, Ok(true)),
+            (r#"contains([1, 2], "1")"#, Ok(false)),
+            (r#"contains("hello", "ell")"#, Ok(true)),
+            (r#"contains("hello", "xyz")"#, Ok(false)),
+            (
+                r#"contains("hello", 1)"#,
+                Err("needle for `contains` must be STRING, got INTEGER"),
+            ),
+            (
+                r#"contains(5, 1)"#,
+                Err("argument to `contains` not supported, got INTEGER"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            match (test_eval(input), expected) {
+                (Object::Boolean(value), Ok(expected)) => {
+                    assert_eq!(value, expected, "input: {}", input)
+                }
+                (Object::Error(err), Err(expected)) => assert_eq!(err, expected),
+                (other, expected) => panic!("expected {:?}, got {:?}", expected, other),
+            }
+        }
+    }
+
     #[test]
     fn test_array_literals() {
         let input = "[1, 2 * 2, 3 + 3]";
@@ -740,7 +2356,12 @@ mod test {
                 Box::new(2_i64),
             ),
             ("[1, 2, 3][3]", Box::new(NULL)), // HERE MOST LANGUAGES WOULD ERROR, WE CHOOSE TO RETURN NULL BY DESIGN SIMPLICITY
-            ("[1, 2, 3][-1]", Box::new(NULL)),
+            ("[1, 2, 3][-1]", Box::new(3_i64)),
+            ("[10, 20, 30][-1]", Box::new(30_i64)),
+            ("[10, 20, 30][-2]", Box::new(20_i64)),
+            ("[10, 20, 30][-3]", Box::new(10_i64)),
+            ("[10, 20, 30][-4]", Box::new(NULL)),
+            ("[][-1]", Box::new(NULL)),
         ];
 
         for test in tests {
@@ -780,15 +2401,15 @@ mod test {
                 ];
 
                 assert_eq!(
-                    hash.pairs.len(),
+                    hash.len(),
                     expected.len(),
                     "hash object has wrong number of pairs. got={}, expected={}",
-                    hash.pairs.len(),
+                    hash.len(),
                     expected.len()
                 );
 
                 for (expected_key, expected_value) in expected {
-                    let pair = match hash.pairs.get(&expected_key.unwrap()) {
+                    let pair = match hash.get(&expected_key.unwrap()) {
                         Some(hash_pair) => hash_pair,
                         None => panic!("no pair for given key in Pairs"),
                     };
@@ -799,6 +2420,523 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_keys_and_values_builtins() {
+        let tests = vec![
+            (
+                r#"keys({"b": 1, "a": 2, 3: 3, true: 4})"#,
+                "[b, a, 3, true]",
+            ),
+            (r#"values({"b": 1, "a": [2], "c": "x"})"#, "[1, [2], x]"),
+            (r#"keys({"x": 1, "y": 2, "x": 3})"#, "[x, y]"),
+            (r#"values({"x": 1, "y": 2, "x": 3})"#, "[3, 2]"),
+            (r#"keys({})"#, "[]"),
+            (r#"{"z": 1, "a": 2}"#, "{z: 1, a: 2}"),
+            (
+                r#"keys([1, 2])"#,
+                "ERROR: argument to `keys` not supported, got ARRAY",
+            ),
+            (
+                r#"values("abc")"#,
+                "ERROR: argument to `values` not supported, got STRING",
+            ),
+            (
+                r#"keys({}, {})"#,
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_delete_builtin() {
+        let tests = vec![
+            (r#"delete({"a": 1, "b": 2, "c": 3}, "b")"#, "{a: 1, c: 3}"),
+            (r#"delete({"a": 1, 2: 2}, 2)"#, "{a: 1}"),
+            (r#"delete({"a": 1}, "missing")"#, "{a: 1}"),
+            (
+                r#"let h = {"a": 1, "b": 2}; let g = delete(h, "a"); h"#,
+                "{a: 1, b: 2}",
+            ),
+            (
+                r#"let h = delete({"a": 1, "b": 2, "c": 3}, "a"); h["c"]"#,
+                "3",
+            ),
+            (
+                r#"delete({"a": 1}, [1])"#,
+                "ERROR: unusable as hash key: ARRAY",
+            ),
+            (
+                r#"delete([1, 2], 0)"#,
+                "ERROR: argument to `delete` not supported, got ARRAY",
+            ),
+            (
+                r#"delete({"a": 1})"#,
+                "ERROR: wrong number of arguments. got=1, want=2",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+        assert_eq!(
+            test_eval(r#"delete({"a": 1}, "missing")"#),
+            test_eval(r#"{"a": 1}"#)
+        );
+    }
+
+    #[test]
+    fn test_assert_builtin() {
+        let tests = vec![
+            ("assert(true)", "null"),
+            ("assert(1 < 2, \"math works\")", "null"),
+            ("assert(0)", "null"),
+            ("assert(1 > 2)", "ERROR: [line 1, col 7] assertion failed"),
+            (
+                "assert(false, \"expected a win\")",
+                "ERROR: [line 1, col 7] assertion failed: expected a win",
+            ),
+            (
+                "assert(if (false) { 1 }, \"null is falsy\")",
+                "ERROR: [line 1, col 7] assertion failed: null is falsy",
+            ),
+            (
+                "assert(true, 5)",
+                "ERROR: [line 1, col 7] second argument to `assert` must be STRING, got INTEGER",
+            ),
+            (
+                "assert()",
+                "ERROR: [line 1, col 7] wrong number of arguments. got=0, want=1 or 2",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = Parser::new(Lexer::new(input)).parse_program();
+            let evaluated = Evaluator::new().eval_program(program);
+            assert_eq!(evaluated.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_chars_builtin() {
+        let tests = vec![
+            (r#"chars("abc")"#, "[a, b, c]"),
+            (r#"chars("héllo")"#, "[h, é, l, l, o]"),
+            (r#"len(chars("世界🌍"))"#, "3"),
+            (r#"chars("")"#, "[]"),
+            (
+                r#"chars(5)"#,
+                "ERROR: argument to `chars` must be STRING, got INTEGER",
+            ),
+            (
+                r#"chars("a", "b")"#,
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+        assert_eq!(test_eval(r#"chars("🌍")[0]"#), test_eval(r#""🌍""#));
+    }
+
+    #[test]
+    fn test_to_chars_and_from_chars_builtins() {
+        let tests = vec![
+            (r#"to_chars("hé")"#, "['h', 'é']"),
+            (r#"to_chars("")"#, "[]"),
+            ("from_chars(['h', 'i'])", r#""hi""#),
+            ("from_chars([])", "\"\""),
+            (r#"from_chars(to_chars("héllo 🌍"))"#, r#""héllo 🌍""#),
+            (
+                r#"from_chars(map(to_chars("abc"), fn(c) { if (c == 'b') { 'B' } else { c } }))"#,
+                r#""aBc""#,
+            ),
+            (
+                "to_chars(['a'])",
+                "ERROR: argument to `to_chars` must be STRING, got ARRAY",
+            ),
+            (
+                r#"from_chars("ab")"#,
+                "ERROR: argument to `from_chars` must be ARRAY, got STRING",
+            ),
+            (
+                r#"from_chars(['a', "b"])"#,
+                "ERROR: elements of the array passed to `from_chars` must be CHAR, got STRING",
+            ),
+            (
+                "from_chars()",
+                "ERROR: wrong number of arguments. got=0, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_apply_builtin() {
+        let tests = vec![
+            ("apply(fn(a, b) { a + b }, [1, 2])", "3"),
+            ("apply(fn() { 7 }, [])", "7"),
+            ("apply(len, [[1, 2, 3]])", "3"),
+            ("apply(fn(first, ...rest) { rest }, [1, 2, 3])", "[2, 3]"),
+            (
+                "apply(fn(a, b) { a + b }, [1])",
+                "ERROR: wrong number of arguments. got=1, want=2",
+            ),
+            (
+                "apply(1, [1])",
+                "ERROR: first argument to `apply` must be FUNCTION, got INTEGER",
+            ),
+            (
+                "apply(len, 1)",
+                "ERROR: second argument to `apply` must be ARRAY, got INTEGER",
+            ),
+            (
+                "apply(len)",
+                "ERROR: wrong number of arguments. got=1, want=2",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_try_builtin() {
+        let tests = vec![
+            ("try(fn() { 1 + 2 })", "3"),
+            ("try(fn() { return 5; 6 })", "5"),
+            (
+                "try(fn() { 1 / 0 })",
+                "ERROR: [line 1, col 14] division by zero",
+            ),
+            (
+                "let r = try(fn() { missing }); if (r) { 1 } else { 2 }; r",
+                "ERROR: [line 1, col 20] identifier not found: missing",
+            ),
+            (
+                "let safe_div = fn(a, b) { try(fn() { a / b }) };
+                [safe_div(6, 3), safe_div(1, 0)]",
+                "[2, ERROR: [line 1, col 40] division by zero]",
+            ),
+            (
+                "try(len)",
+                "ERROR: wrong number of arguments. got=0, want=1",
+            ),
+            (
+                "try(1)",
+                "ERROR: argument to `try` must be FUNCTION, got INTEGER",
+            ),
+            ("try()", "ERROR: wrong number of arguments. got=0, want=1"),
+            ("is_err(try(fn() { 1 / 0 }))", "true"),
+            ("is_ok(try(fn() { 1 / 0 }))", "false"),
+            ("is_err(try(fn() { 1 }))", "false"),
+            (
+                r#"let r = try(fn() { 1 / 0 }); if (is_err(r)) { "recovered" } else { r }"#,
+                r#""recovered""#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_result_and_option_builtins() {
+        let tests = vec![
+            ("ok(5)", r#"{"__variant__": "ok", "value": 5}"#),
+            (
+                r#"err("boom")"#,
+                r#"{"__variant__": "err", "error": "boom"}"#,
+            ),
+            ("none()", r#"{"__variant__": "none"}"#),
+            ("is_ok(ok(5))", "true"),
+            ("is_ok(err(1))", "false"),
+            ("is_err(err(1))", "true"),
+            ("is_err(5)", "false"),
+            ("unwrap(ok(5))", "5"),
+            ("unwrap(some([1]))", "[1]"),
+            (
+                r#"unwrap(err("boom"))"#,
+                "ERROR: unwrap called on err: boom",
+            ),
+            ("unwrap(none())", "ERROR: unwrap called on none"),
+            (
+                "unwrap(5)",
+                "ERROR: argument to `unwrap` must be a result or option, got INTEGER",
+            ),
+            (
+                r#"let parse = fn(s) { if (s == "1") { ok(1) } else { err("bad") } };
+                if (is_ok(parse("x"))) { 1 } else { parse("x")["error"] }"#,
+                r#""bad""#,
+            ),
+            ("ok()", "ERROR: wrong number of arguments. got=0, want=1"),
+            ("none(1)", "ERROR: wrong number of arguments. got=1, want=0"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_case_builtins() {
+        let tests = vec![
+            (r#"upper("Hello, World!")"#, "HELLO, WORLD!"),
+            (r#"lower("Hello, World!")"#, "hello, world!"),
+            (r#"upper("straße")"#, "STRASSE"),
+            (r#"lower("ÀÉÎ")"#, "àéî"),
+            (r#"upper("")"#, ""),
+            (
+                r#"upper("a", "b")"#,
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+            ("lower()", "ERROR: wrong number of arguments. got=0, want=1"),
+            (
+                "lower(true)",
+                "ERROR: argument to `lower` must be STRING, got BOOLEAN",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_trim_builtin() {
+        let tests = vec![
+            (r#"trim("  hi  ")"#, "hi"),
+            ("trim(\"\\t\\n hi there\\r\\n\")", "hi there"),
+            ("trim(\"\\u{A0}hi\\u{3000}\")", "hi"),
+            (r#"trim("hi")"#, "hi"),
+            (r#"trim("   ")"#, ""),
+            (r#"trim("--a-b--", "-")"#, "a-b"),
+            (r#"trim("xyhixy", "yx")"#, "hi"),
+            (r#"trim(" a ", "")"#, " a "),
+            (
+                "trim(1)",
+                "ERROR: argument to `trim` must be STRING, got INTEGER",
+            ),
+            (
+                r#"trim("a", 1)"#,
+                "ERROR: second argument to `trim` must be STRING, got INTEGER",
+            ),
+            (
+                "trim()",
+                "ERROR: wrong number of arguments. got=0, want=1 or 2",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+        assert_eq!(test_eval(r#"trim("hi") == "hi""#), TRUE);
+    }
+
+    #[test]
+    fn test_replace_builtin() {
+        let tests = vec![
+            (r#"replace("a-b-c", "-", "+")"#, "a+b+c"),
+            (r#"replace("aaaa", "aa", "b")"#, "bb"),
+            (r#"replace("hello", "l", "")"#, "heo"),
+            (r#"replace("hello", "xyz", "!")"#, "hello"),
+            (r#"replace("café", "é", "e")"#, "cafe"),
+            (
+                r#"replace("abc", "", "-")"#,
+                "ERROR: pattern for `replace` must not be empty",
+            ),
+            (
+                r#"replace("abc", 1, "-")"#,
+                "ERROR: arguments to `replace` must be STRING, got STRING, INTEGER and STRING",
+            ),
+            (
+                r#"replace("abc", "b")"#,
+                "ERROR: wrong number of arguments. got=2, want=3",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_abs_min_max_builtins() {
+        let tests = vec![
+            ("abs(-5)", "5"),
+            ("abs(5)", "5"),
+            ("abs(0)", "0"),
+            (
+                "abs(-9223372036854775807 - 1)",
+                "ERROR: integer overflow: abs(-9223372036854775808)",
+            ),
+            ("abs()", "ERROR: wrong number of arguments. got=0, want=1"),
+            (
+                "abs(1, 2)",
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+            (
+                "abs(-1.5)",
+                "ERROR: argument to `abs` must be INTEGER, got FLOAT",
+            ),
+            ("min(3, 7)", "3"),
+            ("min(-3, -7)", "-7"),
+            ("max(3, 7)", "7"),
+            ("max(4, 4)", "4"),
+            ("min(1)", "ERROR: wrong number of arguments. got=1, want=2"),
+            (
+                "max(1, 2, 3)",
+                "ERROR: wrong number of arguments. got=3, want=2",
+            ),
+            (
+                r#"min(1, "2")"#,
+                "ERROR: arguments to `min` must be INTEGER, got INTEGER and STRING",
+            ),
+            (
+                "max(true, 2)",
+                "ERROR: arguments to `max` must be INTEGER, got BOOLEAN and INTEGER",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_slice_builtin() {
+        let tests = vec![
+            ("slice([1, 2, 3, 4], 1, 3)", "[2, 3]"),
+            ("slice([1, 2, 3, 4], 0, 4)", "[1, 2, 3, 4]"),
+            ("slice([1, 2, 3], 1, 99)", "[2, 3]"),
+            ("slice([1, 2, 3], -2, 3)", "[2, 3]"),
+            ("slice([1, 2, 3], -99, 1)", "[1]"),
+            ("slice([1, 2, 3], 0, -1)", "[1, 2]"),
+            ("slice([1, 2, 3], 2, 1)", "[]"),
+            ("slice([], 0, 1)", "[]"),
+            ("let a = [1, 2, 3]; let b = slice(a, 0, 2); a", "[1, 2, 3]"),
+            (
+                r#"slice("abc", 0, 1)"#,
+                "ERROR: argument to `slice` not supported, got STRING",
+            ),
+            (
+                r#"slice([1, 2], "0", 1)"#,
+                "ERROR: slice indices must be INTEGER, got STRING and INTEGER",
+            ),
+            (
+                "slice([1, 2], 0, 1.5)",
+                "ERROR: slice indices must be INTEGER, got INTEGER and FLOAT",
+            ),
+            (
+                "slice([1, 2], 0)",
+                "ERROR: wrong number of arguments. got=2, want=3",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_globals_builtin() {
+        let tests = vec![
+            ("globals()", "[]"),
+            (r#"let b = 1; let a = "x"; globals()"#, r#"["a", "b"]"#),
+            (
+                "let a = 1; let f = fn(x) { let y = 2; globals() }; f(0)",
+                r#"["a", "f", "x", "y"]"#,
+            ),
+            ("let a = 1; { let a = 2; globals() }", r#"["a"]"#),
+            (
+                "globals(1)",
+                "ERROR: wrong number of arguments. got=1, want=0",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).inspect(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_index_of_builtin() {
+        let tests = vec![
+            (r#"index_of("hello", "ll")"#, "2"),
+            (r#"index_of("hello", "l")"#, "2"),
+            (r#"index_of("hello", "xyz")"#, "-1"),
+            (r#"index_of("héllo", "llo")"#, "2"),
+            (r#"index_of("abc", "")"#, "0"),
+            ("index_of([1, 2, 3], 2)", "1"),
+            (r#"index_of([1, "two", [3]], [3])"#, "2"),
+            ("index_of([1, 2, 3], 4)", "-1"),
+            (r#"index_of([1, 2], "1")"#, "-1"),
+            (
+                r#"index_of("hello", 1)"#,
+                "ERROR: needle for `index_of` must be STRING, got INTEGER",
+            ),
+            (
+                "index_of(5, 1)",
+                "ERROR: argument to `index_of` not supported, got INTEGER",
+            ),
+            (
+                "index_of([1])",
+                "ERROR: wrong number of arguments. got=1, want=2",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(test_eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_operator_overloading() {
+        let vector = r#"
+            let vec = fn(x, y) {
+                {"x": x, "y": y, "__add__": fn(a, b) { vec(a["x"] + b["x"], a["y"] + b["y"]) }}
+            };
+        "#;
+        let tests = vec![
+            (
+                "let v = vec(1, 2) + vec(10, 20); [v[\"x\"], v[\"y\"]]",
+                "[11, 22]",
+            ),
+            ("let v = vec(1, 2) + vec(1, 1) + vec(1, 1); v[\"y\"]", "4"),
+            (
+                "let scale = {\"__add__\": fn(a, b) { a * 10 }}; 4 + scale",
+                "40",
+            ),
+            (
+                "{\"__add__\": 1} + {}",
+                "ERROR: unknown operator: HASH + HASH",
+            ),
+            ("{} + {}", "ERROR: unknown operator: HASH + HASH"),
+            (
+                "vec(1, 2) - vec(1, 2)",
+                "ERROR: unknown operator: HASH - HASH",
+            ),
+            (
+                "{\"__add__\": fn(a) { a }} + 1",
+                "ERROR: wrong number of arguments. got=2, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let input = format!("{}{}", vector, input);
+            assert_eq!(test_eval(&input).to_string(), expected, "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_hash_index_expressions() {
         let tests: Vec<(&str, Box<dyn any::Any>)> = vec![
@@ -820,6 +2958,30 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_hash_values_of_any_type() {
+        let input = r#"
+        let ops = {
+            "double": fn(x) { x * 2 },
+            "square": fn(x) { x * x },
+            "list": [1, [2, 3]],
+            "nested": {"inner": fn() { 7 }},
+        };
+        ops["double"](5) + ops["square"](3) + ops["list"][1][0] + ops["nested"]["inner"]()
+        "#;
+
+        test_integer_object(test_eval(input), 28);
+
+        match test_eval(r#"{"f": fn(x) { x }}["f"]"#) {
+            Object::Func(function) => assert_eq!(function.parameters.len(), 1),
+            other => panic!("object is not Function, got {:?}", other),
+        }
+        match test_eval(r#"{"k": 1}[fn(x) { x }]"#) {
+            Object::Error(err) => assert!(err.contains("unusable as hash key"), "got {}", err),
+            other => panic!("object is not Error, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_recursive_function() {
         // Regression: a `let`-bound function must be able to call itself.
@@ -831,6 +2993,17 @@ mod test {
         test_integer_object(test_eval(input), 55);
     }
 
+    #[test]
+    fn test_named_recursive_function() {
+        let input = r#"
+        fn factorial(n) {
+            if (n < 2) { 1 } else { n * factorial(n - 1) }
+        }
+        factorial(5);
+        "#;
+        test_integer_object(test_eval(input), 120);
+    }
+
     #[test]
     fn test_empty_array_index() {
         // Regression: indexing an empty array must return NULL, not panic.
@@ -840,10 +3013,11 @@ mod test {
     }
 
     fn test_null_object(obj: Object) {
-        match obj {
-            Object::Null => assert!(true),
-            _ => assert!(false),
-        }
+        assert!(
+            matches!(obj, Object::Null),
+            "object is not Null, got {:?}",
+            obj
+        );
     }
 
     fn test_eval(input: &str) -> Object {
@@ -851,8 +3025,11 @@ mod test {
         let mut parser = Parser::new(lexer);
         let program = parser.parse_program();
 
+        // positions are covered by their own tests; compare bare messages here
         let mut evaluator = Evaluator::new();
-        evaluator.eval_program(program)
+        evaluator
+            .eval(program)
+            .unwrap_or_else(|err| Object::Error(err.message))
     }
 
     fn test_integer_object(obj: Object, expected: i64) {
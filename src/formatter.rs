@@ -0,0 +1,195 @@
+//! Canonical source formatting, built on the AST pretty-printer.
+
+use crate::ast::Node;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+/// Parses `src` and prints it back in the canonical layout: one statement per
+/// line, each ending in `;`, with blocks indented two spaces per level,
+/// operators spaced, and only the parentheses the grouping needs. Formatting
+/// already formatted code returns it unchanged.
+///
+/// Comments in front of a statement, or at the end of the file, are kept on
+/// their own lines; any other comment is dropped. The AST does not record
+/// sugar, so the output spells it out: `xs |> f` prints as `f(xs)`,
+/// `x -> x + 1` as `fn(x) { return x + 1; }` and `s.len()` as `len(s)`.
+///
+/// Source that does not parse is returned untouched as the parser's errors.
+pub fn format_source(src: &str) -> Result<String, Vec<String>> {
+    let mut parser = Parser::new(Lexer::new(src).with_trivia(true));
+    let program = parser.parse_program();
+
+    if !parser.errors().is_empty() {
+        return Err(parser.errors().to_vec());
+    }
+
+    let mut formatted = program.pretty_print(0);
+    // comments after the last statement end up in front of the EOF token
+    for comment in &parser.cur_token.leading_trivia {
+        if !formatted.is_empty() {
+            formatted.push('\n');
+        }
+        formatted.push_str(comment);
+    }
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_source;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_format_function_with_if() {
+        let src = "let max=fn(a,b){if(a>b){return a}else{b}}\nmax(1,2)";
+
+        let expected = "let max = fn(a, b) {
+  if (a > b) {
+    return a;
+  } else {
+    b;
+  }
+};
+max(1, 2);
+";
+
+        assert_eq!(format_source(src), Ok(String::from(expected)));
+    }
+
+    #[test]
+    fn test_format_is_idempotent() {
+        let tests = vec![
+            "let add = fn(a, b) { a + b * 2 }; add(1, 2)",
+            r#"let s = "say \"hi\"\n\t{name}\\"; let r = `C:\new`; let c = '\'';"#,
+            "let h = {\"a\": [1, 2.5], true: -x}; h[\"a\"]; x = !y;",
+            "while (i < 3) { i = i + 1; if (i == 2) { break; } continue; }",
+            "let [a, ...rest] = xs; const K = 1; let y; let z: int = 2;",
+            "match (x) { 1 => \"one\", _ => \"other\" }; quote(1 + unquote(2));",
+            "let m = macro(x) { quote(unquote(x)) }; { let a = 1; a }; fn f(a, ...b) { a }",
+            "(a + b) * c - (d - e); -(x + 1); (-f)(x); (a + b)[0]; x = y = (z = 1) + 2;",
+            "// lead\nlet x = 1; /* mid */ x; if (x) {\n  // inner\n  x\n}\n// tail",
+            "",
+        ];
+
+        for src in tests {
+            let once = format_source(src).unwrap();
+            let twice = format_source(&once).unwrap();
+            assert_eq!(once, twice, "src: {}", src);
+        }
+    }
+
+    #[test]
+    fn test_format_drops_redundant_parentheses() {
+        let tests = vec![
+            ("a + b * c", "a + b * c;\n"),
+            ("(a + b) * c", "(a + b) * c;\n"),
+            ("a - (b - c)", "a - (b - c);\n"),
+            ("(a - b) - c", "a - b - c;\n"),
+            ("!(a == b)", "!(a == b);\n"),
+            ("-a[0]", "-a[0];\n"),
+            ("(-a)[0]", "(-a)[0];\n"),
+            ("(x = 1) + 2", "(x = 1) + 2;\n"),
+            ("s[0]", "s[0];\n"),
+            ("if (a > b) { a }", "if (a > b) {\n  a;\n}\n"),
+        ];
+
+        for (src, expected) in tests {
+            assert_eq!(
+                format_source(src),
+                Ok(String::from(expected)),
+                "src: {}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_keeps_meaning() {
+        let tests = vec![
+            "1 + 2 * 3 - 4 / 2 % 3",
+            "(1 + 2) * (3 - (4 - 5))",
+            "-(-1) + !!true == (1 < 2) != false",
+            "[1, 2][(0 + 1)] * {\"a\": 2}[\"a\"]",
+            "fn(x) { x * 2 }(3) + (fn(x) { x })(1)",
+        ];
+
+        for src in tests {
+            let formatted = format_source(src).unwrap();
+            let mut original = Parser::new(Lexer::new(src));
+            let mut reparsed = Parser::new(Lexer::new(&formatted));
+            assert_eq!(
+                original.parse_program().to_string(),
+                reparsed.parse_program().to_string(),
+                "formatted: {}",
+                formatted
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let src = "// lead\nlet x = 1 + /* dropped */ 2; // kept above y\nfn(){\n/* inner */ y\n// dropped before }\n}\n// tail";
+
+        let expected = "// lead
+let x = 1 + 2;
+// kept above y
+fn() {
+  /* inner */
+  y;
+}
+// tail
+";
+
+        assert_eq!(format_source(src), Ok(String::from(expected)));
+    }
+
+    #[test]
+    fn test_format_spells_out_sugar() {
+        let tests = vec![
+            ("xs |> f", "f(xs);\n"),
+            ("\"x\".len()", "len(\"x\");\n"),
+            ("x -> x + 1", "fn(x) {\n  return x + 1;\n}\n"),
+        ];
+
+        for (src, expected) in tests {
+            assert_eq!(
+                format_source(src),
+                Ok(String::from(expected)),
+                "src: {}",
+                src
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_keeps_string_values() {
+        let src = r#""a\"b\\c\n\u{1}""#;
+
+        assert_eq!(
+            format_source(src),
+            Ok(String::from("\"a\\\"b\\\\c\\n\\u{1}\";\n"))
+        );
+        // raw strings stay raw, so their braces are still not interpolated
+        assert_eq!(format_source(r"`\d{3}`"), Ok(String::from("`\\d{3}`;\n")));
+        // an escaped brace is printed as `{{`, which is still a literal brace
+        assert_eq!(
+            format_source(r#""\x7B1}""#),
+            Ok(String::from("\"{{1}\";\n"))
+        );
+    }
+
+    #[test]
+    fn test_format_reports_parse_errors() {
+        assert_eq!(
+            format_source("let = 1;"),
+            Err(vec![
+                String::from("expected next token to be Ident, got Assign instead"),
+                String::from("no prefix parse function for '=' found"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Token classification for editors: which spans of the source to colour
+//! how. Unlike the parser, it reports comments too.
+
+use crate::lexer::Lexer;
+use crate::token::TokenKind;
+
+/// What a span of source is, as far as a syntax highlighter cares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenCategory {
+    Keyword,
+    Identifier,
+    Number,
+    /// String and character literals.
+    String,
+    Operator,
+    Comment,
+    /// Brackets and separators.
+    Punctuation,
+}
+
+/// Splits `src` into `(start, end, category)` spans, where `start..end` is a
+/// byte range, in source order. Whitespace and text the lexer rejects (an
+/// unterminated string, a stray character) are not covered by any span.
+pub fn classify(src: &str) -> Vec<(usize, usize, TokenCategory)> {
+    let mut spans = Vec::new();
+    let mut lexer = Lexer::new(src);
+    let mut gap_start = 0;
+
+    loop {
+        let (token, range) = lexer.next_spanned_token();
+        // the lexer skipped only whitespace and comments before this token
+        push_comments(src, gap_start, range.start, &mut spans);
+        gap_start = range.end;
+
+        let category = match token.kind {
+            TokenKind::EOF => return spans,
+            TokenKind::Illegal => continue,
+            TokenKind::Ident => TokenCategory::Identifier,
+            TokenKind::Int | TokenKind::Float => TokenCategory::Number,
+            TokenKind::String | TokenKind::RawString | TokenKind::Char => TokenCategory::String,
+            kind if kind.is_keyword() => TokenCategory::Keyword,
+            kind if kind.is_operator() => TokenCategory::Operator,
+            _ => TokenCategory::Punctuation,
+        };
+        spans.push((range.start, range.end, category));
+    }
+}
+
+/// Adds a span for each comment in `src[start..end]`, which holds nothing but
+/// whitespace and comments.
+fn push_comments(
+    src: &str,
+    start: usize,
+    end: usize,
+    spans: &mut Vec<(usize, usize, TokenCategory)>,
+) {
+    let gap = &src[start..end];
+    let mut offset = 0;
+
+    while offset < gap.len() {
+        let rest = &gap[offset..];
+        let length = if rest.starts_with("//") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            // an unterminated block comment runs to the end of the gap
+            rest.find("*/").map_or(rest.len(), |close| close + 2)
+        } else {
+            offset += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        };
+        spans.push((
+            start + offset,
+            start + offset + length,
+            TokenCategory::Comment,
+        ));
+        offset += length;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{classify, TokenCategory};
+
+    #[test]
+    fn test_classify() {
+        let src = "let x = 2.5; // half\nif (x) { \"s\" } /* é */ 'c'";
+
+        let expected = vec![
+            ("let", TokenCategory::Keyword),
+            ("x", TokenCategory::Identifier),
+            ("=", TokenCategory::Operator),
+            ("2.5", TokenCategory::Number),
+            (";", TokenCategory::Punctuation),
+            ("// half", TokenCategory::Comment),
+            ("if", TokenCategory::Keyword),
+            ("(", TokenCategory::Punctuation),
+            ("x", TokenCategory::Identifier),
+            (")", TokenCategory::Punctuation),
+            ("{", TokenCategory::Punctuation),
+            ("\"s\"", TokenCategory::String),
+            ("}", TokenCategory::Punctuation),
+            ("/* é */", TokenCategory::Comment),
+            ("'c'", TokenCategory::String),
+        ];
+
+        let spans: Vec<(&str, TokenCategory)> = classify(src)
+            .into_iter()
+            .map(|(start, end, category)| (&src[start..end], category))
+            .collect();
+        assert_eq!(spans, expected);
+    }
+
+    #[test]
+    fn test_classify_skips_illegal_text() {
+        let src = "a @ \"\\q\" /* open";
+
+        assert_eq!(
+            classify(src),
+            vec![
+                (0, 1, TokenCategory::Identifier),
+                (9, 16, TokenCategory::Comment)
+            ]
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+/// A pool of shared strings. Interning the same text twice hands back two
+/// handles to one allocation, so repeated identifiers and keywords cost a
+/// reference-count bump instead of a fresh `String`.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: HashSet<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, text: &str) -> Rc<str> {
+        if let Some(existing) = self.strings.get(text) {
+            return Rc::clone(existing);
+        }
+        let interned: Rc<str> = Rc::from(text);
+        self.strings.insert(Rc::clone(&interned));
+        interned
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use super::Interner;
+
+    #[test]
+    fn test_intern_shares_storage() {
+        let mut interner = Interner::new();
+
+        let first = interner.intern("counter");
+        let second = interner.intern("counter");
+        let other = interner.intern("total");
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert!(!Rc::ptr_eq(&first, &other));
+        assert_eq!(interner.len(), 2);
+    }
+}
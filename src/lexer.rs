@@ -1,20 +1,67 @@
-use crate::token::{lookup_keywords, Token, TokenKind};
+use std::borrow::Cow;
+use std::mem;
+use std::ops::Range;
+use std::rc::Rc;
 
-pub struct Lexer {
-    input: Vec<char>,
+use crate::interner::Interner;
+use crate::token::{lookup_keywords, Position, Token, TokenKind};
+
+/// Scans borrowed source text. `position` and `read_position` are byte offsets
+/// into `input`, so slices of it can be taken without copying.
+pub struct Lexer<'a> {
+    input: &'a str,
     position: usize,
     read_position: usize,
     ch: char,
+    line: usize,
+    column: usize,
+    /// Identifiers and keywords share storage across every token that spells them.
+    interner: Interner,
+    /// Whether skipped comments are kept as trivia; see [`Lexer::with_trivia`].
+    capture_trivia: bool,
+    /// Comments skipped since the last token, when they are being kept.
+    trivia: Vec<String>,
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Lexer {
-        let vec_of_chars = input.chars().collect();
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Lexer<'a> {
         let mut lexer = Lexer {
-            input: vec_of_chars,
+            input,
             position: 0,
             read_position: 0,
             ch: Default::default(), // this initializes the char to '\0' which is a null character
+            line: 1,
+            column: 0,
+            interner: Interner::new(),
+            capture_trivia: false,
+            trivia: Vec::new(),
+        };
+
+        lexer.read_char();
+
+        lexer
+    }
+
+    /// Keeps the comments the lexer skips, attaching them to the next token as
+    /// its `leading_trivia` instead of dropping them. Off by default.
+    pub fn with_trivia(mut self, enabled: bool) -> Self {
+        self.capture_trivia = enabled;
+        self
+    }
+
+    /// Starts lexing `input` as if it continued earlier text: the first
+    /// character sits at `position`, and `interner` is reused.
+    fn resume(input: &'a str, position: Position, interner: Interner) -> Lexer<'a> {
+        let mut lexer = Lexer {
+            input,
+            position: 0,
+            read_position: 0,
+            ch: Default::default(),
+            line: position.line,
+            column: position.column - 1,
+            interner,
+            capture_trivia: false,
+            trivia: Vec::new(),
         };
 
         lexer.read_char();
@@ -22,77 +69,114 @@ impl Lexer {
         lexer
     }
     fn read_char(&mut self) {
-        if self.read_position >= self.input.len() {
-            self.ch = '\0';
+        if self.ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            self.ch = self.input[self.read_position];
+            self.column += 1;
         }
+        self.ch = self.peek_char();
         self.position = self.read_position;
-        self.read_position += 1;
+        // past the end we still step by one so `position` keeps moving forward
+        self.read_position += self.ch.len_utf8();
     }
     pub fn next_token(&mut self) -> Token {
+        self.next_spanned_token().0
+    }
+
+    /// Like [`Lexer::next_token`], but also returns the byte range of the
+    /// source the token was read from. `EOF` gets an empty range at the end.
+    pub fn next_spanned_token(&mut self) -> (Token, Range<usize>) {
         self.skip_whitespace_and_comments();
 
+        let start = self.position.min(self.input.len());
+        let position = Position {
+            line: self.line,
+            column: self.column,
+        };
+        let mut token = self.read_token();
+        token.position = position;
+        token.leading_trivia = mem::take(&mut self.trivia);
+        (token, start..self.position.min(self.input.len()))
+    }
+    fn read_token(&mut self) -> Token {
         let token = match self.ch {
             '=' => {
                 if self.peek_char() == '=' {
                     self.read_char();
-                    Token {
-                        kind: TokenKind::EQ,
-                        literal: "==".to_string(),
-                    }
+                    self.token(TokenKind::EQ, "==")
+                } else if self.peek_char() == '>' {
+                    self.read_char();
+                    self.token(TokenKind::FatArrow, "=>")
                 } else {
-                    Lexer::new_token(TokenKind::Assign, self.ch)
+                    self.new_token(TokenKind::Assign, self.ch)
+                }
+            }
+            ';' => self.new_token(TokenKind::Semicolon, self.ch),
+            ':' => self.new_token(TokenKind::Colon, self.ch),
+            '(' => self.new_token(TokenKind::LParen, self.ch),
+            ')' => self.new_token(TokenKind::RParen, self.ch),
+            ',' => self.new_token(TokenKind::Comma, self.ch),
+            '+' => self.new_token(TokenKind::Plus, self.ch),
+            '{' => self.new_token(TokenKind::LBrace, self.ch),
+            '}' => self.new_token(TokenKind::RBrace, self.ch),
+            // returning before `read_char` keeps the lexer parked at the end,
+            // so every later call yields the same EOF
+            '\0' => return self.token(TokenKind::EOF, ""),
+            '-' => {
+                if self.peek_char() == '>' {
+                    self.read_char();
+                    self.token(TokenKind::Arrow, "->")
+                } else {
+                    self.new_token(TokenKind::Minus, self.ch)
                 }
             }
-            ';' => Lexer::new_token(TokenKind::Semicolon, self.ch),
-            ':' => Lexer::new_token(TokenKind::Colon, self.ch),
-            '(' => Lexer::new_token(TokenKind::LParen, self.ch),
-            ')' => Lexer::new_token(TokenKind::RParen, self.ch),
-            ',' => Lexer::new_token(TokenKind::Comma, self.ch),
-            '+' => Lexer::new_token(TokenKind::Plus, self.ch),
-            '{' => Lexer::new_token(TokenKind::LBrace, self.ch),
-            '}' => Lexer::new_token(TokenKind::RBrace, self.ch),
-            '\0' => Token {
-                kind: TokenKind::EOF,
-                literal: "".to_string(),
-            },
-            '-' => Lexer::new_token(TokenKind::Minus, self.ch),
             '!' => {
                 if self.peek_char() == '=' {
                     self.read_char();
-                    Token {
-                        kind: TokenKind::NotEQ,
-                        literal: "!=".to_string(),
-                    }
+                    self.token(TokenKind::NotEQ, "!=")
                 } else {
-                    Lexer::new_token(TokenKind::Bang, self.ch)
+                    self.new_token(TokenKind::Bang, self.ch)
                 }
             }
-            '*' => Lexer::new_token(TokenKind::Asterisk, self.ch),
-            '/' => Lexer::new_token(TokenKind::Slash, self.ch),
-            '<' => Lexer::new_token(TokenKind::LT, self.ch),
-            '>' => Lexer::new_token(TokenKind::GT, self.ch),
-            '"' => Token {
-                kind: TokenKind::String,
-                literal: self.read_string(),
-            },
-            '[' => Lexer::new_token(TokenKind::LBracket, self.ch),
-            ']' => Lexer::new_token(TokenKind::RBracket, self.ch),
+            '*' => self.new_token(TokenKind::Asterisk, self.ch),
+            '/' => self.new_token(TokenKind::Slash, self.ch),
+            '%' => self.new_token(TokenKind::Percent, self.ch),
+            '<' => self.new_token(TokenKind::LT, self.ch),
+            '>' => self.new_token(TokenKind::GT, self.ch),
+            // string contents are rarely repeated, so they skip the interner
+            '"' => string_token(TokenKind::String, self.read_string()),
+            '`' => string_token(TokenKind::RawString, self.read_raw_string()),
+            '\'' => match self.read_char_literal() {
+                Ok(ch) => self.new_token(TokenKind::Char, ch),
+                Err(message) => self.token(TokenKind::Illegal, &message),
+            },
+            '.' => {
+                if self.peek_char() == '.' && self.peek_char_at(1) == '.' {
+                    self.read_char();
+                    self.read_char();
+                    self.token(TokenKind::Ellipsis, "...")
+                } else {
+                    self.new_token(TokenKind::Dot, self.ch)
+                }
+            }
+            // only `|>` is an operator; a lone `|` is illegal
+            '|' if self.peek_char() == '>' => {
+                self.read_char();
+                self.token(TokenKind::Pipe, "|>")
+            }
+            '[' => self.new_token(TokenKind::LBracket, self.ch),
+            ']' => self.new_token(TokenKind::RBracket, self.ch),
             _ => {
-                return if Lexer::is_letter(self.ch) {
-                    let literal = self.read_identifier();
-                    let kind = lookup_keywords(&literal);
-                    Token { kind, literal }
+                if Lexer::is_letter(self.ch) {
+                    let identifier = self.read_identifier();
+                    return self.token(lookup_keywords(identifier), identifier);
                 } else if Lexer::is_digit(self.ch) {
-                    let literal = self.read_number();
-                    return Token {
-                        kind: TokenKind::Int,
-                        literal,
-                    };
-                } else {
-                    return Lexer::new_token(TokenKind::Illegal, self.ch);
+                    let (kind, literal) = self.read_number();
+                    return self.token(kind, literal);
                 }
+                // falls through to `read_char` so the lexer moves past the stray character
+                self.new_token(TokenKind::Illegal, self.ch)
             }
         };
 
@@ -100,35 +184,57 @@ impl Lexer {
 
         token
     }
-    fn new_token(kind: TokenKind, ch: char) -> Token {
+    fn new_token(&mut self, kind: TokenKind, ch: char) -> Token {
+        let mut buf = [0; 4];
+        self.token(kind, ch.encode_utf8(&mut buf))
+    }
+
+    /// Builds a token with an interned literal. `next_token` fills in the position.
+    fn token(&mut self, kind: TokenKind, literal: &str) -> Token {
         Token {
             kind,
-            literal: ch.to_string(),
+            literal: self.interner.intern(literal),
+            ..Default::default()
         }
     }
+    /// Whether `ch` can start an identifier: any Unicode letter or `_`.
     fn is_letter(ch: char) -> bool {
-        ch.is_ascii_alphabetic() || ch == '_'
+        ch.is_alphabetic() || ch == '_'
     }
-    fn read_identifier(&mut self) -> String {
-        let mut identifier = String::new();
-        while Lexer::is_letter(self.ch) {
-            identifier.push(self.ch);
+
+    /// Whether `ch` can continue an identifier once it has started. Digits are
+    /// allowed here, so `x1` is one identifier while `1x` is a number then a name.
+    fn is_identifier_char(ch: char) -> bool {
+        ch.is_alphanumeric() || ch == '_'
+    }
+    fn read_identifier(&mut self) -> &'a str {
+        let position = self.position;
+        while Lexer::is_identifier_char(self.ch) {
             self.read_char();
         }
-        identifier
+        &self.input[position..self.position]
     }
 
     fn skip_whitespace_and_comments(&mut self) {
         loop {
-            while self.ch.is_ascii_whitespace() {
+            // any Unicode whitespace separates tokens; only `\n` starts a
+            // new line, which `read_char` counts
+            while self.ch.is_whitespace() {
                 self.read_char();
             }
+            let start = self.position;
             // line comment: `//` to end of line
             if self.ch == '/' && self.peek_char() == '/' {
                 self.skip_comment();
+            } else if self.ch == '/' && self.peek_char() == '*' {
+                self.skip_block_comment();
             } else {
                 break;
             }
+            if self.capture_trivia {
+                let end = self.position.min(self.input.len());
+                self.trivia.push(self.input[start..end].to_string());
+            }
         }
     }
 
@@ -138,45 +244,315 @@ impl Lexer {
         }
     }
 
+    /// Skips `/* ... */`. An unterminated block comment runs to the end of input.
+    fn skip_block_comment(&mut self) {
+        self.read_char();
+        self.read_char();
+        while self.ch != '\0' && !(self.ch == '*' && self.peek_char() == '/') {
+            self.read_char();
+        }
+        self.read_char();
+        self.read_char();
+    }
+
     fn is_digit(ch: char) -> bool {
         ch.is_ascii_digit()
     }
 
-    fn read_number(&mut self) -> String {
-        let mut number = String::new();
+    /// Reads an integer, or a float when the digits are followed by a `.` and
+    /// more digits. `1.len()` stays an integer followed by a method call.
+    fn read_number(&mut self) -> (TokenKind, &'a str) {
+        let position = self.position;
+        let mut kind = TokenKind::Int;
         while Lexer::is_digit(self.ch) {
-            number.push(self.ch);
             self.read_char();
         }
-        number
+        if self.ch == '.' && Lexer::is_digit(self.peek_char()) {
+            kind = TokenKind::Float;
+            self.read_char();
+            while Lexer::is_digit(self.ch) {
+                self.read_char();
+            }
+        }
+        (kind, &self.input[position..self.position])
     }
 
-    fn read_string(&mut self) -> String {
+    /// Reads a string literal up to its closing quote, decoding escapes. The
+    /// text is borrowed from the source unless it contains an escape. A brace
+    /// written as an escape is doubled, so interpolation reads it as a literal
+    /// brace. A malformed escape still consumes the whole literal, and the
+    /// first one found is returned as the error.
+    fn read_string(&mut self) -> Result<Cow<'a, str>, String> {
         let position = self.position + 1;
+        let mut decoded: Option<String> = None;
+        let mut error = None;
         self.read_char();
 
         while self.ch != '"' && self.ch != '\0' {
+            if self.ch == '\\' {
+                let out = decoded.get_or_insert_with(|| self.input[position..self.position].into());
+                match self.read_escape() {
+                    Ok(ch @ ('{' | '}')) => {
+                        out.push(ch);
+                        out.push(ch);
+                    }
+                    Ok(ch) => out.push(ch),
+                    Err(message) => {
+                        error.get_or_insert(message);
+                    }
+                }
+            } else if let Some(out) = &mut decoded {
+                out.push(self.ch);
+            }
             self.read_char();
         }
 
-        let string_slice = &self.input[position..self.position];
-        string_slice.iter().collect()
+        match (error, decoded) {
+            (Some(message), _) => Err(message),
+            (None, Some(decoded)) => Ok(Cow::Owned(decoded)),
+            (None, None) => Ok(Cow::Borrowed(&self.input[position..self.position])),
+        }
+    }
+
+    /// Reads a backtick-delimited string verbatim: no escapes are decoded, so
+    /// `` `C:\new` `` keeps its backslash. It may span lines, but must be
+    /// closed before the end of input.
+    fn read_raw_string(&mut self) -> Result<Cow<'a, str>, String> {
+        let position = self.position + 1;
+        self.read_char();
+
+        while self.ch != '`' {
+            if self.ch == '\0' {
+                return Err(String::from("unterminated raw string"));
+            }
+            self.read_char();
+        }
+
+        Ok(Cow::Borrowed(&self.input[position..self.position]))
+    }
+
+    /// Reads a character literal such as `'a'` or `'\n'`: exactly one
+    /// character or escape between single quotes. A literal left open runs to
+    /// the end of the line.
+    fn read_char_literal(&mut self) -> Result<char, String> {
+        let position = self.position + 1;
+        let mut chars = Vec::new();
+        let mut error = None;
+        self.read_char();
+
+        while self.ch != '\'' && self.ch != '\n' && self.ch != '\0' {
+            if self.ch == '\\' {
+                match self.read_escape() {
+                    Ok(ch) => chars.push(ch),
+                    Err(message) => {
+                        error.get_or_insert(message);
+                    }
+                }
+            } else {
+                chars.push(self.ch);
+            }
+            self.read_char();
+        }
+
+        let text = &self.input[position..self.position];
+        if self.ch != '\'' {
+            return Err(format!("unterminated character literal '{}", text));
+        }
+        if let Some(message) = error {
+            return Err(message);
+        }
+        match chars.as_slice() {
+            [ch] => Ok(*ch),
+            [] => Err(String::from("empty character literal ''")),
+            _ => Err(format!(
+                "character literal '{}' must hold exactly one character",
+                text
+            )),
+        }
+    }
+
+    /// Decodes the escape starting at the current `\`, leaving `ch` on its
+    /// last character. Supports `\n`, `\t`, `\r`, `\\`, `\"`, `\'`, `\xHH`
+    /// for ASCII and `\u{H...}` for any Unicode scalar value.
+    fn read_escape(&mut self) -> Result<char, String> {
+        self.read_char();
+        match self.ch {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            'x' => {
+                let mut digits = String::new();
+                for _ in 0..2 {
+                    if !self.peek_char().is_ascii_hexdigit() {
+                        break;
+                    }
+                    self.read_char();
+                    digits.push(self.ch);
+                }
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 && byte.is_ascii() => Ok(char::from(byte)),
+                    _ => Err(format!(
+                        "invalid hex escape '\\x{}', expected two hex digits up to 7F",
+                        self.peek_escape_rest(digits)
+                    )),
+                }
+            }
+            'u' => {
+                if self.peek_char() != '{' {
+                    return Err(String::from(
+                        "invalid unicode escape '\\u', expected '\\u{...}'",
+                    ));
+                }
+                self.read_char();
+                let mut digits = String::new();
+                while self.peek_char() != '}' && self.peek_char() != '"' && self.peek_char() != '\0'
+                {
+                    self.read_char();
+                    digits.push(self.ch);
+                }
+                if self.peek_char() != '}' {
+                    return Err(format!("unterminated unicode escape '\\u{{{}'", digits));
+                }
+                self.read_char();
+                if digits.is_empty() {
+                    return Err(String::from("empty unicode escape '\\u{}'"));
+                }
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() <= 6)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid unicode escape '\\u{{{}}}', not a Unicode scalar value",
+                            digits
+                        )
+                    })
+            }
+            '\0' => Err(String::from("unterminated escape at end of input")),
+            other => Err(format!("unknown escape '\\{}'", other)),
+        }
+    }
+
+    /// Extends the hex digits already read with the next two characters for
+    /// an error message, so `\xZZ` is reported whole.
+    fn peek_escape_rest(&self, mut digits: String) -> String {
+        for offset in 0..2 - digits.len().min(2) {
+            match self.peek_char_at(offset) {
+                '"' | '\0' => break,
+                ch => digits.push(ch),
+            }
+        }
+        digits
     }
 
     fn peek_char(&self) -> char {
-        if self.read_position >= self.input.len() {
-            '\0'
-        } else {
-            self.input[self.read_position]
+        self.peek_char_at(0)
+    }
+
+    /// Looks `offset` characters past the next one without consuming anything.
+    fn peek_char_at(&self, offset: usize) -> char {
+        match self.input.get(self.read_position..) {
+            Some(rest) => rest.chars().nth(offset).unwrap_or('\0'),
+            None => '\0',
+        }
+    }
+}
+
+/// Turns the result of reading a string literal into its token; a malformed
+/// literal becomes `Illegal` with the reason as its literal.
+fn string_token(kind: TokenKind, literal: Result<Cow<'_, str>, String>) -> Token {
+    match literal {
+        Ok(literal) => Token {
+            kind,
+            literal: Rc::from(literal.as_ref()),
+            ..Default::default()
+        },
+        Err(message) => Token {
+            kind: TokenKind::Illegal,
+            literal: message.into(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Lexes source that arrives in pieces, e.g. one line at a time from a
+/// `BufRead`. Tokens come back as soon as nothing later in the input could
+/// change them; a string, identifier or block comment that is still open at
+/// the end of a chunk waits for the next one.
+pub struct ChunkedLexer {
+    /// Input received but not yet turned into tokens.
+    pending: String,
+    /// Where the first character of `pending` sits in the whole source.
+    position: Position,
+    interner: Interner,
+}
+
+impl Default for ChunkedLexer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkedLexer {
+    pub fn new() -> ChunkedLexer {
+        ChunkedLexer {
+            pending: String::new(),
+            position: Position { line: 1, column: 1 },
+            interner: Interner::new(),
+        }
+    }
+
+    /// Appends `chunk` and returns every token that is now complete.
+    pub fn feed(&mut self, chunk: &str) -> Vec<Token> {
+        self.pending.push_str(chunk);
+        self.drain(false)
+    }
+
+    /// Lexes whatever is left, ending with `EOF`.
+    pub fn finish(mut self) -> Vec<Token> {
+        self.drain(true)
+    }
+
+    fn drain(&mut self, at_end: bool) -> Vec<Token> {
+        let interner = std::mem::take(&mut self.interner);
+        let mut lexer = Lexer::resume(&self.pending, self.position, interner);
+        let mut tokens = Vec::new();
+        let mut consumed = 0;
+
+        loop {
+            let token = lexer.next_token();
+            let is_eof = token.kind == TokenKind::EOF;
+            // the longest lookahead (`...`) needs two characters past the
+            // token, so only trust tokens that have both available
+            let lookahead = lexer.input.get(lexer.position..).unwrap_or_default();
+            if !at_end && lookahead.chars().nth(1).is_none() {
+                break;
+            }
+            tokens.push(token);
+            consumed = lexer.position;
+            self.position = Position {
+                line: lexer.line,
+                column: lexer.column,
+            };
+            if is_eof {
+                break;
+            }
         }
+
+        self.interner = std::mem::take(&mut lexer.interner);
+        self.pending.drain(..consumed.min(self.pending.len()));
+        tokens
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::token::{Token, TokenKind};
+    use crate::token::{Position, Token, TokenKind};
 
-    use super::Lexer;
+    use super::{ChunkedLexer, Lexer};
 
     #[test]
     fn test_next_token() {
@@ -189,7 +565,7 @@ mod test {
             };
 
             let result = add(five, ten);
-            !-/*5;
+            !-/ *5;
             5 < 10 > 5;
 
             if (5 < 10) {
@@ -211,395 +587,493 @@ mod test {
         let expected: Vec<Token> = vec![
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "five".to_string(),
+                literal: "five".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "ten".to_string(),
+                literal: "ten".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "add".to_string(),
+                literal: "add".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Function,
-                literal: "fn".to_string(),
+                literal: "fn".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LParen,
-                literal: "(".to_string(),
+                literal: "(".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "x".to_string(),
+                literal: "x".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Comma,
-                literal: ",".to_string(),
+                literal: ",".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "y".to_string(),
+                literal: "y".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RParen,
-                literal: ")".to_string(),
+                literal: ")".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LBrace,
-                literal: "{".to_string(),
+                literal: "{".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "x".to_string(),
+                literal: "x".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Plus,
-                literal: "+".to_string(),
+                literal: "+".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "y".to_string(),
+                literal: "y".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RBrace,
-                literal: "}".to_string(),
+                literal: "}".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "result".to_string(),
+                literal: "result".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "add".to_string(),
+                literal: "add".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LParen,
-                literal: "(".to_string(),
+                literal: "(".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "five".to_string(),
+                literal: "five".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Comma,
-                literal: ",".to_string(),
+                literal: ",".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "ten".to_string(),
+                literal: "ten".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RParen,
-                literal: ")".to_string(),
+                literal: ")".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Bang,
-                literal: "!".to_string(),
+                literal: "!".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Minus,
-                literal: "-".to_string(),
+                literal: "-".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Slash,
-                literal: "/".to_string(),
+                literal: "/".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Asterisk,
-                literal: "*".to_string(),
+                literal: "*".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LT,
-                literal: "<".to_string(),
+                literal: "<".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::GT,
-                literal: ">".to_string(),
+                literal: ">".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::If,
-                literal: "if".to_string(),
+                literal: "if".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LParen,
-                literal: "(".to_string(),
+                literal: "(".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LT,
-                literal: "<".to_string(),
+                literal: "<".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RParen,
-                literal: ")".to_string(),
+                literal: ")".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LBrace,
-                literal: "{".to_string(),
+                literal: "{".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Return,
-                literal: "return".to_string(),
+                literal: "return".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::True,
-                literal: "true".to_string(),
+                literal: "true".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RBrace,
-                literal: "}".to_string(),
+                literal: "}".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Else,
-                literal: "else".to_string(),
+                literal: "else".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LBrace,
-                literal: "{".to_string(),
+                literal: "{".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Return,
-                literal: "return".to_string(),
+                literal: "return".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::False,
-                literal: "false".to_string(),
+                literal: "false".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RBrace,
-                literal: "}".to_string(),
+                literal: "}".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::EQ,
-                literal: "==".to_string(),
+                literal: "==".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::NotEQ,
-                literal: "!=".to_string(),
+                literal: "!=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "9".to_string(),
+                literal: "9".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::String,
-                literal: "foobar".to_string(),
+                literal: "foobar".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::String,
-                literal: "foo bar".to_string(),
+                literal: "foo bar".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LBracket,
-                literal: "[".to_string(),
+                literal: "[".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "1".to_string(),
+                literal: "1".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Comma,
-                literal: ",".to_string(),
+                literal: ",".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "2".to_string(),
+                literal: "2".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RBracket,
-                literal: "]".to_string(),
+                literal: "]".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::LBrace,
-                literal: "{".to_string(),
+                literal: "{".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::String,
-                literal: "foo".to_string(),
+                literal: "foo".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Colon,
-                literal: ":".to_string(),
+                literal: ":".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::String,
-                literal: "bar".to_string(),
+                literal: "bar".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::RBrace,
-                literal: "}".to_string(),
+                literal: "}".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "five".to_string(),
+                literal: "five".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "5".to_string(),
+                literal: "5".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Let,
-                literal: "let".to_string(),
+                literal: "let".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Ident,
-                literal: "ten".to_string(),
+                literal: "ten".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Assign,
-                literal: "=".to_string(),
+                literal: "=".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Int,
-                literal: "10".to_string(),
+                literal: "10".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::Semicolon,
-                literal: ";".to_string(),
+                literal: ";".into(),
+                ..Default::default()
             },
             Token {
                 kind: TokenKind::EOF,
-                literal: "".to_string(),
+                literal: "".into(),
+                ..Default::default()
             },
         ];
 
@@ -620,4 +1094,531 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn test_arrow() {
+        let input = "x -> x-1 - > y->";
+
+        let expected = vec![
+            (TokenKind::Ident, "x"),
+            (TokenKind::Arrow, "->"),
+            (TokenKind::Ident, "x"),
+            (TokenKind::Minus, "-"),
+            (TokenKind::Int, "1"),
+            (TokenKind::Minus, "-"),
+            (TokenKind::GT, ">"),
+            (TokenKind::Ident, "y"),
+            (TokenKind::Arrow, "->"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_pipe() {
+        let input = "x |> f | > ||>";
+
+        let expected = vec![
+            (TokenKind::Ident, "x"),
+            (TokenKind::Pipe, "|>"),
+            (TokenKind::Ident, "f"),
+            (TokenKind::Illegal, "|"),
+            (TokenKind::GT, ">"),
+            (TokenKind::Illegal, "|"),
+            (TokenKind::Pipe, "|>"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        let input = "fn(a, ...rest) . ..x";
+
+        let expected = vec![
+            (TokenKind::Function, "fn"),
+            (TokenKind::LParen, "("),
+            (TokenKind::Ident, "a"),
+            (TokenKind::Comma, ","),
+            (TokenKind::Ellipsis, "..."),
+            (TokenKind::Ident, "rest"),
+            (TokenKind::RParen, ")"),
+            (TokenKind::Dot, "."),
+            (TokenKind::Dot, "."),
+            (TokenKind::Dot, "."),
+            (TokenKind::Ident, "x"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(
+                kind, received_token.kind,
+                "tests[{}] - token type wrong. expected={}, got={}",
+                idx, kind, received_token.kind
+            );
+            assert_eq!(
+                literal, &*received_token.literal,
+                "tests[{}] - literal wrong. expected={}, got={}",
+                idx, literal, received_token.literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_token_positions() {
+        let input = "let x = 5;\n  x + 10";
+
+        let expected = vec![
+            ("let", 1, 1),
+            ("x", 1, 5),
+            ("=", 1, 7),
+            ("5", 1, 9),
+            (";", 1, 10),
+            ("x", 2, 3),
+            ("+", 2, 5),
+            ("10", 2, 7),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (literal, line, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                Position { line, column },
+                received_token.position,
+                "tests[{}] - position wrong for {}",
+                idx,
+                literal
+            );
+        }
+    }
+
+    #[test]
+    fn test_crlf_line_endings() {
+        let input = "let x = 5;\r\nx + 10;\r\n\r\n// note\r\ny";
+
+        let expected = vec![
+            ("let", 1, 1),
+            ("x", 1, 5),
+            ("=", 1, 7),
+            ("5", 1, 9),
+            (";", 1, 10),
+            ("x", 2, 1),
+            ("+", 2, 3),
+            ("10", 2, 5),
+            (";", 2, 7),
+            ("y", 5, 1),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (literal, line, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                Position { line, column },
+                received_token.position,
+                "tests[{}] - \\r\\n must count as one line break",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_byte_string_literal() {
+        let input = r#"let s = "héllo, 世界 🌍"; s"#;
+
+        let expected = vec![
+            (TokenKind::Let, "let", 1),
+            (TokenKind::Ident, "s", 5),
+            (TokenKind::Assign, "=", 7),
+            (TokenKind::String, "héllo, 世界 🌍", 9),
+            (TokenKind::Semicolon, ";", 22),
+            (TokenKind::Ident, "s", 24),
+            (TokenKind::EOF, "", 25),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                column, received_token.position.column,
+                "tests[{}] - columns count characters, not bytes",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let tests = vec![
+            (r#""\x41\x7a""#, "Az"),
+            (r#""smile \u{1F600}!""#, "smile 😀!"),
+            (r#""\u{e9}t\u{E9}""#, "été"),
+            (r#""a\tb\nc\\d\"e\r""#, "a\tb\nc\\d\"e\r"),
+            (r#""\x7B1\u{7D}""#, "{{1}}"),
+        ];
+
+        for (input, expected) in tests {
+            let token = Lexer::new(input).next_token();
+            assert_eq!(TokenKind::String, token.kind, "input: {}", input);
+            assert_eq!(expected, &*token.literal, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_malformed_string_escapes() {
+        let tests = vec![
+            (
+                r#""\xZZ""#,
+                r"invalid hex escape '\xZZ', expected two hex digits up to 7F",
+            ),
+            (
+                r#""\x4""#,
+                r"invalid hex escape '\x4', expected two hex digits up to 7F",
+            ),
+            (
+                r#""\xFF""#,
+                r"invalid hex escape '\xFF', expected two hex digits up to 7F",
+            ),
+            (r#""\u{}""#, r"empty unicode escape '\u{}'"),
+            (
+                r#""\u{110000}""#,
+                r"invalid unicode escape '\u{110000}', not a Unicode scalar value",
+            ),
+            (
+                r#""\u{D800}""#,
+                r"invalid unicode escape '\u{D800}', not a Unicode scalar value",
+            ),
+            (
+                r#""\u41""#,
+                r"invalid unicode escape '\u', expected '\u{...}'",
+            ),
+            (r#""\u{41""#, r"unterminated unicode escape '\u{41'"),
+            (r#""\q \xZZ""#, r"unknown escape '\q'"),
+        ];
+
+        for (input, expected) in tests {
+            let mut lexer = Lexer::new(input);
+            let token = lexer.next_token();
+            assert_eq!(TokenKind::Illegal, token.kind, "input: {}", input);
+            assert_eq!(expected, token.illegal_message(), "input: {}", input);
+            assert_eq!(
+                TokenKind::EOF,
+                lexer.next_token().kind,
+                "the rest of the literal is skipped for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let input = "`C:\\new\\t \"{x}\"\nnext` + `";
+
+        let expected = vec![
+            (TokenKind::RawString, "C:\\new\\t \"{x}\"\nnext", 1, 1),
+            (TokenKind::Plus, "+", 2, 7),
+            (TokenKind::Illegal, "unterminated raw string", 2, 9),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal, line, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                Position { line, column },
+                received_token.position,
+                "tests[{}]",
+                idx
+            );
+        }
+        assert_eq!(TokenKind::EOF, lexer.next_token().kind);
+    }
+
+    #[test]
+    fn test_char_literals() {
+        let input = "'a' '\\n' '\\'' 'é' '' 'ab' '\\q' 'x\ny";
+
+        let expected = vec![
+            (TokenKind::Char, "a", 1, 1),
+            (TokenKind::Char, "\n", 1, 5),
+            (TokenKind::Char, "'", 1, 10),
+            (TokenKind::Char, "é", 1, 15),
+            (TokenKind::Illegal, "empty character literal ''", 1, 19),
+            (
+                TokenKind::Illegal,
+                "character literal 'ab' must hold exactly one character",
+                1,
+                22,
+            ),
+            (TokenKind::Illegal, "unknown escape '\\q'", 1, 27),
+            (
+                TokenKind::Illegal,
+                "unterminated character literal 'x",
+                1,
+                32,
+            ),
+            (TokenKind::Ident, "y", 2, 1),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal, line, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                Position { line, column },
+                received_token.position,
+                "tests[{}]",
+                idx
+            );
+        }
+        assert_eq!(TokenKind::EOF, lexer.next_token().kind);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let input = "let café = 1; naïve_π2 🌍 x";
+
+        let expected = vec![
+            (TokenKind::Let, "let"),
+            (TokenKind::Ident, "café"),
+            (TokenKind::Assign, "="),
+            (TokenKind::Int, "1"),
+            (TokenKind::Semicolon, ";"),
+            (TokenKind::Ident, "naïve_π2"),
+            (TokenKind::Illegal, "🌍"),
+            (TokenKind::Ident, "x"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_unicode_whitespace() {
+        // no-break space, ideographic space and a line separator
+        let input = "let\u{A0}x\u{3000}=\u{2028}5;\n\u{A0}x";
+
+        let expected = vec![
+            (TokenKind::Let, "let", 1, 1),
+            (TokenKind::Ident, "x", 1, 5),
+            (TokenKind::Assign, "=", 1, 7),
+            (TokenKind::Int, "5", 1, 9),
+            (TokenKind::Semicolon, ";", 1, 10),
+            (TokenKind::Ident, "x", 2, 2),
+            (TokenKind::EOF, "", 2, 3),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal, line, column)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+            assert_eq!(
+                Position { line, column },
+                received_token.position,
+                "tests[{}]",
+                idx
+            );
+        }
+    }
+
+    #[test]
+    fn test_identifiers_with_digits() {
+        let input = "foo1 _a1b2 var2 1foo";
+
+        let expected = vec![
+            (TokenKind::Ident, "foo1"),
+            (TokenKind::Ident, "_a1b2"),
+            (TokenKind::Ident, "var2"),
+            (TokenKind::Int, "1"),
+            (TokenKind::Ident, "foo"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_float_literals() {
+        let input = "3.14 10.0 1.len 2.";
+
+        let expected = vec![
+            (TokenKind::Float, "3.14"),
+            (TokenKind::Float, "10.0"),
+            (TokenKind::Int, "1"),
+            (TokenKind::Dot, "."),
+            (TokenKind::Ident, "len"),
+            (TokenKind::Int, "2"),
+            (TokenKind::Dot, "."),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_illegal_character_position() {
+        let mut lexer = Lexer::new("x\n  @ y");
+
+        lexer.next_token();
+        let illegal = lexer.next_token();
+
+        assert_eq!(TokenKind::Illegal, illegal.kind);
+        assert_eq!("@", &*illegal.literal);
+        assert_eq!(Position { line: 2, column: 3 }, illegal.position);
+        assert_eq!("unexpected character '@'", illegal.unexpected_character());
+        assert_eq!(TokenKind::Ident, lexer.next_token().kind);
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let input = "1 /* skipped\n * entirely */ + 2 /* unterminated";
+
+        let expected = vec![
+            (TokenKind::Int, "1"),
+            (TokenKind::Plus, "+"),
+            (TokenKind::Int, "2"),
+            (TokenKind::EOF, ""),
+        ];
+
+        let mut lexer = Lexer::new(input);
+
+        for (idx, (kind, literal)) in expected.into_iter().enumerate() {
+            let received_token = lexer.next_token();
+            assert_eq!(kind, received_token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*received_token.literal, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_comment_trivia() {
+        let input = "// doc\n/* a */ /* b */\nlet x = 1; // trailing";
+
+        let mut lexer = Lexer::new(input).with_trivia(true);
+        let let_token = lexer.next_token();
+        assert_eq!(TokenKind::Let, let_token.kind);
+        assert_eq!(
+            let_token.leading_trivia,
+            vec!["// doc", "/* a */", "/* b */"]
+        );
+
+        let rest: Vec<Token> = (0..5).map(|_| lexer.next_token()).collect();
+        assert!(rest[..4]
+            .iter()
+            .all(|token| token.leading_trivia.is_empty()));
+        assert_eq!(TokenKind::EOF, rest[4].kind);
+        assert_eq!(rest[4].leading_trivia, vec!["// trailing"]);
+
+        // off by default
+        let token = Lexer::new(input).next_token();
+        assert_eq!(TokenKind::Let, token.kind);
+        assert!(token.leading_trivia.is_empty());
+    }
+
+    #[test]
+    fn test_chunked_lexer() {
+        let mut lexer = ChunkedLexer::new();
+
+        let mut tokens = lexer.feed("let total = 1 /* a block");
+        assert_eq!(
+            vec!["let", "total", "=", "1"],
+            tokens.iter().map(|t| &*t.literal).collect::<Vec<_>>(),
+            "tokens that might still grow are held back"
+        );
+
+        tokens.extend(lexer.feed(" comment */ + \"two"));
+        tokens.extend(lexer.feed(" words\";\nx"));
+        tokens.extend(lexer.finish());
+
+        let expected = vec![
+            (TokenKind::Let, "let", 1, 1),
+            (TokenKind::Ident, "total", 1, 5),
+            (TokenKind::Assign, "=", 1, 11),
+            (TokenKind::Int, "1", 1, 13),
+            (TokenKind::Plus, "+", 1, 37),
+            (TokenKind::String, "two words", 1, 39),
+            (TokenKind::Semicolon, ";", 1, 50),
+            (TokenKind::Ident, "x", 2, 1),
+            (TokenKind::EOF, "", 2, 2),
+        ];
+
+        assert_eq!(expected.len(), tokens.len());
+        for (idx, ((kind, literal, line, column), token)) in
+            expected.into_iter().zip(tokens).enumerate()
+        {
+            assert_eq!(kind, token.kind, "tests[{}] - kind wrong", idx);
+            assert_eq!(literal, &*token.literal, "tests[{}]", idx);
+            assert_eq!(Position { line, column }, token.position, "tests[{}]", idx);
+        }
+    }
+
+    #[test]
+    fn test_eof_is_idempotent() {
+        let mut lexer = Lexer::new("5");
+
+        assert_eq!(TokenKind::Int, lexer.next_token().kind);
+
+        for idx in 0..5 {
+            let received_token = lexer.next_token();
+            assert_eq!(
+                TokenKind::EOF,
+                received_token.kind,
+                "call {} past the end",
+                idx
+            );
+            assert_eq!("", &*received_token.literal, "call {} past the end", idx);
+            assert_eq!(
+                Position { line: 1, column: 2 },
+                received_token.position,
+                "call {} past the end",
+                idx
+            );
+        }
+    }
 }
@@ -8,9 +8,16 @@
 
 pub mod ast;
 pub mod builtins;
+pub mod cli;
 pub mod evaluator;
+pub mod formatter;
+pub mod highlight;
+pub mod interner;
 pub mod lexer;
+pub mod macro_expansion;
 pub mod object;
+pub mod optimizer;
 pub mod parser;
 pub mod repl;
 pub mod token;
+pub mod visitor;
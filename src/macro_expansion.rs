@@ -0,0 +1,219 @@
+//! Macros: definitions are collected and call sites expanded on the parsed
+//! program, before any of it is evaluated.
+
+use std::mem;
+
+use crate::ast::{CallExpression, ExpressionNode, LetStatement, Program, StatementNode};
+use crate::evaluator::{EvalError, Evaluator};
+use crate::object::{Env, Function, Object};
+use crate::optimizer::{walk_expression, Rewrite};
+
+/// Removes every top-level `let name = macro(...) { ... };` from `program` and
+/// binds the macro in `env`. A macro literal anywhere else is an ordinary
+/// value and is never expanded.
+pub fn define_macros(program: &mut Program, env: &Env) {
+    for stmt in mem::take(&mut program.statements) {
+        match stmt {
+            StatementNode::Let(LetStatement {
+                name,
+                value: ExpressionNode::Macro(macro_lit),
+                ..
+            }) => {
+                let macro_fn = Function {
+                    parameters: macro_lit.parameters,
+                    body: macro_lit.body,
+                    env: env.clone(),
+                    variadic: false,
+                };
+                env.borrow_mut().set(name.value, Object::Macro(macro_fn));
+            }
+            stmt => program.statements.push(stmt),
+        }
+    }
+}
+
+/// Replaces each call to a macro bound in `evaluator`'s scope with the AST the
+/// macro returns. The arguments are expanded before being handed over, but
+/// the returned AST is not expanded again.
+pub fn expand_macros(program: Program, evaluator: &mut Evaluator) -> Result<Program, EvalError> {
+    let mut expander = MacroExpander {
+        evaluator,
+        error: None,
+    };
+    let program = expander.rewrite_program(program);
+
+    match expander.error {
+        Some(err) => Err(err),
+        None => Ok(program),
+    }
+}
+
+/// Expands macro calls, stopping at the first error.
+struct MacroExpander<'e> {
+    evaluator: &'e mut Evaluator,
+    error: Option<EvalError>,
+}
+
+impl MacroExpander<'_> {
+    fn macro_called_by(&self, call_exp: &CallExpression) -> Option<Function> {
+        match &*call_exp.function {
+            ExpressionNode::IdentifierNode(ident) => {
+                match self.evaluator.env().borrow().get(&ident.value) {
+                    Some(Object::Macro(macro_fn)) => Some(macro_fn),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Rewrite for MacroExpander<'_> {
+    fn rewrite_expression(&mut self, expression: ExpressionNode) -> ExpressionNode {
+        let expression = walk_expression(self, expression);
+        let call_exp = match expression {
+            ExpressionNode::Call(call_exp) if self.error.is_none() => call_exp,
+            expression => return expression,
+        };
+
+        match self.macro_called_by(&call_exp) {
+            Some(macro_fn) => {
+                let position = call_exp.token.position;
+                match self.evaluator.apply_macro(macro_fn, call_exp.arguments) {
+                    Ok(node) => node,
+                    Err(err) => {
+                        self.error = Some(err.or_at(position));
+                        ExpressionNode::None
+                    }
+                }
+            }
+            None => ExpressionNode::Call(call_exp),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{define_macros, expand_macros};
+    use crate::ast::Program;
+    use crate::evaluator::Evaluator;
+    use crate::object::{Environment, Object};
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn parse(input: &str) -> Program {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "parser errors: {:?}",
+            parser.errors()
+        );
+        program
+    }
+
+    #[test]
+    fn test_define_macros() {
+        let mut program = parse(
+            "let number = 1;
+            let function = fn(x, y) { x + y };
+            let mymacro = macro(x, y) { x + y; };",
+        );
+        let env = Environment::new_environment();
+
+        define_macros(&mut program, &env);
+
+        assert_eq!(program.statements.len(), 2);
+        assert!(env.borrow().get("number").is_none());
+        assert!(env.borrow().get("function").is_none());
+        let mymacro = env.borrow().get("mymacro");
+        match mymacro {
+            Some(Object::Macro(macro_fn)) => {
+                let params: Vec<_> = macro_fn.parameters.iter().map(|p| &*p.value).collect();
+                assert_eq!(params, vec!["x", "y"]);
+                assert_eq!(macro_fn.body.to_string(), "(x + y)");
+            }
+            other => panic!("mymacro is not a Macro, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_expand_macros() {
+        let tests = vec![
+            (
+                "let infixExpression = macro() { quote(1 + 2); };
+                infixExpression();",
+                "(1 + 2)",
+            ),
+            (
+                "let reverse = macro(a, b) { quote(unquote(b) - unquote(a)); };
+                reverse(2 + 2, 10 - 5);",
+                "(10 - 5) - (2 + 2)",
+            ),
+            (
+                r#"let unless = macro(condition, consequence, alternative) {
+                    quote(if (!(unquote(condition))) {
+                        unquote(consequence);
+                    } else {
+                        unquote(alternative);
+                    });
+                };
+                unless(10 > 5, log("not greater"), log("greater"));"#,
+                r#"if (!(10 > 5)) { log("not greater") } else { log("greater") }"#,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new();
+            let mut program = parse(input);
+            define_macros(&mut program, &evaluator.env());
+            let expanded = expand_macros(program, &mut evaluator).unwrap();
+
+            assert_eq!(
+                expanded.to_string(),
+                parse(expected).to_string(),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_macros_run_before_evaluation() {
+        let input = "let unless = macro(cond, then, otherwise) {
+                quote(if (!(unquote(cond))) { unquote(then) } else { unquote(otherwise) });
+            };
+            unless(1 > 2, 10, missing)";
+
+        let mut evaluator = Evaluator::new();
+        assert_eq!(evaluator.eval(parse(input)), Ok(Object::Integer(10)));
+
+        // definitions persist, so later programs can use the macro
+        assert_eq!(
+            evaluator.eval(parse("unless(true, 1, 2)")),
+            Ok(Object::Integer(2))
+        );
+    }
+
+    #[test]
+    fn test_macro_errors() {
+        let tests = vec![
+            (
+                "let m = macro(x) { 1 }; m(2)",
+                "[line 1, col 26] macro must return a quote, got INTEGER",
+            ),
+            (
+                "let m = macro(x) { quote(x) }; m(1, 2)",
+                "[line 1, col 33] wrong number of arguments. got=2, want=1",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut evaluator = Evaluator::new();
+            match evaluator.eval(parse(input)) {
+                Err(err) => assert_eq!(err.to_string(), expected, "input: {}", input),
+                Ok(obj) => panic!("expected an error, got {:?} for {}", obj, input),
+            }
+        }
+    }
+}
@@ -1,8 +1,30 @@
+use std::{env, io, process};
+
+use guedzlang::cli::{self, Command};
 use guedzlang::repl::start;
-use std::io;
 
-fn main() -> std::io::Result<()> {
-    println!("\n\nHello!! This is the GuedzLang REPL...");
-    println!("Feel free to type in commands");
-    start(io::stdin(), io::stdout())
+fn main() -> io::Result<()> {
+    let command = match cli::parse_args(env::args().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("{}\n{}", message, cli::USAGE);
+            process::exit(1);
+        }
+    };
+
+    match command {
+        Command::Repl => {
+            println!("\n\nHello!! This is the GuedzLang REPL...");
+            println!("Feel free to type in commands");
+            start()
+        }
+        Command::Eval(code) => {
+            let code = cli::run_source(&code, &mut io::stdout(), &mut io::stderr())?;
+            process::exit(code);
+        }
+        Command::File(path) => {
+            let code = cli::run_file(&path, io::stdin(), &mut io::stdout(), &mut io::stderr())?;
+            process::exit(code);
+        }
+    }
 }
@@ -1,17 +1,17 @@
 use std::{
     cell::RefCell,
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     fmt::{self, Display, Formatter},
-    hash::{DefaultHasher, Hash, Hasher},
     rc::Rc,
 };
 
 use crate::{
-    ast::{BlockStatement, Identifier},
+    ast::{format_parameters, BlockStatement, ExpressionNode, Identifier},
     builtins::Builtins,
+    evaluator::{EvalError, Evaluator},
 };
 
-pub type BuiltinFunction = fn(Vec<Object>) -> Object;
+pub type BuiltinFunction = fn(&mut Evaluator, Vec<Object>) -> Result<Object, EvalError>;
 pub type Env = Rc<RefCell<Environment>>;
 
 pub const TRUE: Object = Object::Boolean(true);
@@ -21,14 +21,25 @@ pub const NULL: Object = Object::Null;
 #[derive(Debug, Clone)]
 pub enum Object {
     Integer(i64),
+    Float(f64),
     Boolean(bool),
     ReturnValue(Box<Object>),
+    /// Signals raised by `break` and `continue`. Like `ReturnValue` they stop
+    /// the blocks they pass through until the enclosing loop handles them.
+    Break,
+    Continue,
     Error(String),
     Func(Function),
+    /// A macro defined with `let name = macro(...) { ... }`. Its arguments are
+    /// passed as quotes and it must return one.
+    Macro(Function),
     StringObj(String),
+    Char(char),
     Builtin(BuiltinFunction),
     Array(Vec<Object>),
     HashObj(HashStruct),
+    /// An unevaluated expression produced by `quote(...)`.
+    Quote(ExpressionNode),
     Null,
 }
 
@@ -36,36 +47,161 @@ impl Object {
     pub fn object_type(&self) -> &'static str {
         match self {
             Self::Integer(_) => "INTEGER",
+            Self::Float(_) => "FLOAT",
             Self::Boolean(_) => "BOOLEAN",
             Self::ReturnValue(_) => "RETURN_VALUE",
+            Self::Break => "BREAK",
+            Self::Continue => "CONTINUE",
             Self::Error(_) => "ERROR",
             Self::Func(_) => "FUNCTION",
+            Self::Macro(_) => "MACRO",
             Self::StringObj(_) => "STRING",
+            Self::Char(_) => "CHAR",
             Self::Builtin(_) => "BUILTIN",
             Self::Array(_) => "ARRAY",
             Self::HashObj(_) => "HASH",
+            Self::Quote(_) => "QUOTE",
             Self::Null => "NULL",
         }
     }
 }
 
+impl Object {
+    /// How the REPL echoes a value. Unlike `Display`, which prints strings
+    /// as-is for `log` and interpolation, strings are quoted here, including
+    /// inside arrays and hashes, so `"1"` and `1` are told apart.
+    pub fn inspect(&self) -> String {
+        match self {
+            Self::StringObj(str) => format!("{:?}", str),
+            Self::Char(ch) => format!("{:?}", ch),
+            Self::ReturnValue(ret_value) => ret_value.inspect(),
+            Self::Array(elements) => {
+                let elems = elements
+                    .iter()
+                    .map(Object::inspect)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("[{}]", elems)
+            }
+            Self::HashObj(hash) => {
+                let pairs = hash
+                    .iter()
+                    .map(|pair| format!("{}: {}", pair.key.inspect(), pair.value.inspect()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{{{}}}", pairs)
+            }
+            _ => self.to_string(),
+        }
+    }
+
+    /// A JSON rendering such as `{"type":"INTEGER","value":5}`, for tools
+    /// consuming REPL output. Arrays hold the JSON of their elements, hashes a
+    /// list of `{"key":…,"value":…}` pairs (keys need not be strings), and
+    /// functions only report their type.
+    pub fn to_json(&self) -> String {
+        let value = match self {
+            Self::Integer(value) => value.to_string(),
+            Self::Float(value) => format!("{:?}", value),
+            Self::Boolean(value) => value.to_string(),
+            Self::StringObj(str) => json_string(str),
+            Self::Char(ch) => json_string(&ch.to_string()),
+            Self::Error(message) => json_string(message),
+            Self::Quote(node) => json_string(&node.to_string()),
+            Self::ReturnValue(ret_value) => return ret_value.to_json(),
+            Self::Array(elements) => {
+                let elems = elements
+                    .iter()
+                    .map(Object::to_json)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("[{}]", elems)
+            }
+            Self::HashObj(hash) => {
+                let pairs = hash
+                    .iter()
+                    .map(|pair| {
+                        format!(
+                            r#"{{"key":{},"value":{}}}"#,
+                            pair.key.to_json(),
+                            pair.value.to_json()
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",");
+                format!("[{}]", pairs)
+            }
+            Self::Null => String::from("null"),
+            Self::Func(_) | Self::Macro(_) | Self::Builtin(_) | Self::Break | Self::Continue => {
+                return format!(r#"{{"type":"{}"}}"#, self.object_type())
+            }
+        };
+        format!(r#"{{"type":"{}","value":{}}}"#, self.object_type(), value)
+    }
+}
+
+/// Quotes `str` as a JSON string literal.
+fn json_string(str: &str) -> String {
+    let mut out = String::with_capacity(str.len() + 2);
+    out.push('"');
+    for ch in str.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch.is_control() => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Integer(left), Self::Integer(right)) => left == right,
+            (Self::Float(left), Self::Float(right)) => left == right,
+            (Self::Boolean(left), Self::Boolean(right)) => left == right,
+            (Self::StringObj(left), Self::StringObj(right)) => left == right,
+            (Self::Char(left), Self::Char(right)) => left == right,
+            (Self::Array(left), Self::Array(right)) => left == right,
+            (Self::HashObj(left), Self::HashObj(right)) => left == right,
+            (Self::ReturnValue(left), Self::ReturnValue(right)) => left == right,
+            (Self::Error(left), Self::Error(right)) => left == right,
+            (Self::Null, Self::Null) => true,
+            (Self::Break, Self::Break) | (Self::Continue, Self::Continue) => true,
+            (Self::Quote(left), Self::Quote(right)) => left.to_string() == right.to_string(),
+            (Self::Func(left), Self::Func(right)) => left.same_definition(right),
+            (Self::Macro(left), Self::Macro(right)) => left.same_definition(right),
+            (Self::Builtin(left), Self::Builtin(right)) => std::ptr::fn_addr_eq(*left, *right),
+            _ => false,
+        }
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Self::Integer(value) => write!(f, "{}", value),
+            Self::Float(value) => write!(f, "{:?}", value),
             Self::Boolean(value) => write!(f, "{}", value),
             Self::ReturnValue(ret_value) => write!(f, "{}", ret_value),
+            Self::Break => write!(f, "break"),
+            Self::Continue => write!(f, "continue"),
             Self::Error(message) => write!(f, "ERROR: {}", message),
             Self::Func(function) => {
-                let params = function
-                    .parameters
-                    .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                write!(f, "fn({}) {{\n{}\n}}", params, function.body)
+                let params = format_parameters(&function.parameters, function.variadic);
+                write!(f, "fn({}) {{ {} }}", params, function.body)
+            }
+            Self::Macro(function) => {
+                let params = format_parameters(&function.parameters, function.variadic);
+                write!(f, "macro({}) {{ {} }}", params, function.body)
             }
             Self::StringObj(str) => write!(f, "{}", str),
+            Self::Char(ch) => write!(f, "{}", ch),
             Self::Array(elements) => {
                 let elems = elements
                     .iter()
@@ -77,13 +213,13 @@ impl Display for Object {
             Self::Builtin(_) => write!(f, "builtin function"),
             Self::HashObj(hash) => {
                 let pairs = hash
-                    .pairs
-                    .values()
+                    .iter()
                     .map(|pair| format!("{}: {}", pair.key, pair.value))
                     .collect::<Vec<_>>()
                     .join(", ");
                 write!(f, "{{{}}}", pairs)
             }
+            Self::Quote(node) => write!(f, "QUOTE({})", node),
             Self::Null => write!(f, "null"),
         }
     }
@@ -91,10 +227,20 @@ impl Display for Object {
 
 #[derive(Debug, Clone)]
 pub struct Environment {
-    pub store: HashMap<String, Object>,
+    pub store: HashMap<Rc<str>, Object>,
+    /// Names in `store` that were bound with `const`.
+    constants: HashSet<Rc<str>>,
     pub outer: Option<Env>,
 }
 
+/// The bindings of one scope at a point in time, taken with
+/// [`Environment::snapshot`] and put back with [`Environment::restore`].
+#[derive(Debug, Clone)]
+pub struct EnvSnapshot {
+    store: HashMap<Rc<str>, Object>,
+    constants: HashSet<Rc<str>>,
+}
+
 impl Environment {
     pub fn new_environment() -> Env {
         let mut env_map = HashMap::new();
@@ -102,15 +248,16 @@ impl Environment {
 
         Rc::new(RefCell::new(Environment {
             store: env_map,
+            constants: HashSet::new(),
             outer: None,
         }))
     }
 
-    fn init_builtins(hashmap: &mut HashMap<String, Object>) {
+    fn init_builtins(hashmap: &mut HashMap<Rc<str>, Object>) {
         let builtins_functions = Builtins;
         let builtins = builtins_functions.all_builtins();
         for (name, builtin) in builtins {
-            hashmap.insert(name, builtin);
+            hashmap.insert(name.into(), builtin);
         }
     }
 
@@ -118,6 +265,7 @@ impl Environment {
         let env_map = HashMap::new();
         Rc::new(RefCell::new(Environment {
             store: env_map,
+            constants: HashSet::new(),
             outer: Some(outer),
         }))
     }
@@ -132,9 +280,78 @@ impl Environment {
         }
     }
 
-    pub fn set(&mut self, name: String, value: Object) {
+    pub fn set(&mut self, name: Rc<str>, value: Object) {
         self.store.insert(name, value);
     }
+
+    /// Binds `name` in this scope so that [`Environment::assign`] refuses to
+    /// change it.
+    pub fn set_constant(&mut self, name: Rc<str>, value: Object) {
+        self.constants.insert(name.clone());
+        self.store.insert(name, value);
+    }
+
+    /// Whether `name` is a constant bound directly in this scope.
+    pub fn is_constant(&self, name: &str) -> bool {
+        self.constants.contains(name)
+    }
+
+    /// Rebinds `name` in the innermost scope that defines it. Fails if the
+    /// name is unbound or was declared with `const`.
+    pub fn assign(&mut self, name: &str, value: Object) -> Result<(), String> {
+        if let Some(slot) = self.store.get_mut(name) {
+            if self.constants.contains(name) {
+                return Err(format!("cannot assign to constant '{}'", name));
+            }
+            *slot = value;
+            return Ok(());
+        }
+        match &self.outer {
+            Some(outer) => outer.borrow_mut().assign(name, value),
+            None => Err(format!("identifier not found: {}", name)),
+        }
+    }
+
+    /// Records the bindings made directly in this scope, constants included.
+    /// Outer scopes are not captured; snapshot each one that should roll back.
+    pub fn snapshot(&self) -> EnvSnapshot {
+        EnvSnapshot {
+            store: self.store.clone(),
+            constants: self.constants.clone(),
+        }
+    }
+
+    /// Puts this scope back exactly as it was when `snapshot` was taken:
+    /// names bound since are removed and overwritten names get their old
+    /// values back. The link to the outer scope is left alone.
+    pub fn restore(&mut self, snapshot: EnvSnapshot) {
+        self.store = snapshot.store;
+        self.constants = snapshot.constants;
+    }
+
+    /// The bindings made directly in this scope, sorted by name. Outer scopes
+    /// and builtin functions are left out.
+    pub fn bindings(&self) -> Vec<(String, Object)> {
+        let mut bindings: Vec<(String, Object)> = self
+            .store
+            .iter()
+            .filter(|(_, value)| !matches!(value, Object::Builtin(_)))
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        bindings.sort_by(|(left, _), (right, _)| left.cmp(right));
+        bindings
+    }
+
+    /// Every name that resolves from this scope, including those of outer
+    /// scopes, sorted and without duplicates. Builtin functions are left out.
+    pub fn visible_names(&self) -> Vec<String> {
+        let mut names: BTreeSet<String> =
+            self.bindings().into_iter().map(|(name, _)| name).collect();
+        if let Some(outer) = &self.outer {
+            names.extend(outer.borrow().visible_names());
+        }
+        names.into_iter().collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -142,12 +359,33 @@ pub struct Function {
     pub parameters: Vec<Identifier>,
     pub body: BlockStatement,
     pub env: Env,
+    pub variadic: bool,
+}
+
+impl Function {
+    /// Functions are equal only when they are the same definition closing
+    /// over the same scope.
+    fn same_definition(&self, other: &Function) -> bool {
+        Rc::ptr_eq(&self.env, &other.env)
+            && self.variadic == other.variadic
+            && self.parameters.len() == other.parameters.len()
+            && self
+                .parameters
+                .iter()
+                .zip(&other.parameters)
+                .all(|(l, r)| l.value == r.value)
+            && self.body.to_string() == other.body.to_string()
+    }
 }
 
+/// The identity of a hash key. It keeps the key's type and full value, so
+/// `1` and `true` are distinct keys and two strings only share a key when
+/// they are equal.
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
-pub struct HashKey {
-    pub object_type: &'static str,
-    pub value: i64,
+pub enum HashKey {
+    Integer(i64),
+    Boolean(bool),
+    String(String),
 }
 
 pub trait Hashable {
@@ -157,42 +395,166 @@ pub trait Hashable {
 impl Hashable for Object {
     fn hash_key(&self) -> Result<HashKey, String> {
         match self {
-            Self::Boolean(bool) => Ok(HashKey {
-                object_type: self.object_type(),
-                value: if *bool { 1 } else { 0 },
-            }),
-            Self::Integer(int) => Ok(HashKey {
-                object_type: self.object_type(),
-                value: *int,
-            }),
-            Self::StringObj(string) => {
-                let mut hasher = DefaultHasher::new();
-                string.hash(&mut hasher);
-                Ok(HashKey {
-                    object_type: self.object_type(),
-                    value: hasher.finish() as i64,
-                })
-            }
+            Self::Boolean(bool) => Ok(HashKey::Boolean(*bool)),
+            Self::Integer(int) => Ok(HashKey::Integer(*int)),
+            Self::StringObj(string) => Ok(HashKey::String(string.clone())),
             _ => Err(format!("unusable as hash key: {}", self.object_type())),
         }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct HashPair {
     pub key: Object,
     pub value: Object,
 }
 
-#[derive(Debug, Clone)]
+/// A hash that remembers insertion order, so iterating it (and printing it,
+/// and the `keys`/`values` builtins) always lists pairs in the order their
+/// keys were first added. Re-inserting an existing key replaces the value in
+/// place. Equality ignores order.
+#[derive(Debug, Clone, Default)]
 pub struct HashStruct {
-    pub pairs: HashMap<HashKey, HashPair>,
+    pairs: Vec<HashPair>,
+    index: HashMap<HashKey, usize>,
+}
+
+impl HashStruct {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: HashKey, pair: HashPair) {
+        match self.index.get(&key) {
+            Some(&idx) => self.pairs[idx] = pair,
+            None => {
+                self.index.insert(key, self.pairs.len());
+                self.pairs.push(pair);
+            }
+        }
+    }
+
+    /// Removes `key`, keeping the remaining pairs in their original order.
+    pub fn remove(&mut self, key: &HashKey) -> Option<HashPair> {
+        let idx = self.index.remove(key)?;
+        for later in self.index.values_mut() {
+            if *later > idx {
+                *later -= 1;
+            }
+        }
+        Some(self.pairs.remove(idx))
+    }
+
+    pub fn get(&self, key: &HashKey) -> Option<&HashPair> {
+        self.index.get(key).map(|&idx| &self.pairs[idx])
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// The pairs in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = &HashPair> {
+        self.pairs.iter()
+    }
+}
+
+impl PartialEq for HashStruct {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .index
+                .iter()
+                .all(|(key, &idx)| other.get(key) == Some(&self.pairs[idx]))
+    }
 }
 
 #[cfg(test)]
 mod test {
 
-    use super::{Hashable, Object};
+    use super::{Environment, Hashable, Object};
+    use crate::{evaluator::Evaluator, lexer::Lexer, parser::Parser};
+
+    fn eval(input: &str) -> Object {
+        let program = Parser::new(Lexer::new(input)).parse_program();
+        Evaluator::new().eval_program(program)
+    }
+
+    #[test]
+    fn test_object_type_names() {
+        let tests = vec![
+            (Object::Integer(1), "INTEGER"),
+            (Object::Float(1.5), "FLOAT"),
+            (Object::Boolean(true), "BOOLEAN"),
+            (Object::StringObj(String::from("s")), "STRING"),
+            (Object::Char('c'), "CHAR"),
+            (Object::Array(vec![]), "ARRAY"),
+            (eval("{}"), "HASH"),
+            (eval("fn() {}"), "FUNCTION"),
+            (Object::Error(String::from("oops")), "ERROR"),
+            (Object::ReturnValue(Box::new(Object::Null)), "RETURN_VALUE"),
+            (Object::Break, "BREAK"),
+            (Object::Continue, "CONTINUE"),
+            (eval("len"), "BUILTIN"),
+            (eval("macro(x) { x }"), "MACRO"),
+            (eval("quote(1 + 2)"), "QUOTE"),
+            (Object::Null, "NULL"),
+        ];
+
+        for (object, expected) in tests {
+            assert_eq!(object.object_type(), expected, "object: {:?}", object);
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let tests = vec![
+            ("fn(x, y) { x + y }", "fn(x, y) { (x + y) }"),
+            ("fn(first, ...rest) { rest }", "fn(first, ...rest) { rest }"),
+            ("[1, 2, 3]", "[1, 2, 3]"),
+            ("[]", "[]"),
+            ("[1, [true, \"a\"]]", "[1, [true, a]]"),
+            ("len", "builtin function"),
+            (r#"{"a": 1}"#, "{a: 1}"),
+            (
+                r#"{"z": 1, 2: "two", true: [3]}"#,
+                "{z: 1, 2: two, true: [3]}",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input).to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_environment_snapshot_and_restore() {
+        let env = Environment::new_environment();
+        env.borrow_mut().set("kept".into(), Object::Integer(1));
+        env.borrow_mut().set("changed".into(), Object::Integer(2));
+        env.borrow_mut()
+            .set_constant("fixed".into(), Object::Integer(3));
+        let before = env.borrow().bindings();
+
+        let snapshot = env.borrow().snapshot();
+        env.borrow_mut().set("added".into(), Object::Integer(4));
+        env.borrow_mut().set("changed".into(), Object::Integer(5));
+        env.borrow_mut().set("fixed".into(), Object::Integer(6));
+        env.borrow_mut()
+            .set_constant("kept".into(), Object::Integer(7));
+        env.borrow_mut().restore(snapshot);
+
+        assert_eq!(env.borrow().bindings(), before);
+        assert!(env.borrow().get("added").is_none());
+        assert!(env.borrow().get("len").is_some());
+        assert!(env.borrow().is_constant("fixed"));
+        assert!(!env.borrow().is_constant("kept"));
+        assert_eq!(env.borrow_mut().assign("kept", Object::Integer(8)), Ok(()));
+    }
 
     #[test]
     fn test_string_hash_key() {
@@ -213,4 +575,99 @@ mod test {
             "strings with different content have same hash keys"
         );
     }
+
+    #[test]
+    fn test_hash_keys_keep_their_type() {
+        assert_ne!(
+            Object::Integer(1).hash_key(),
+            Object::Boolean(true).hash_key()
+        );
+        assert_ne!(
+            Object::Integer(0).hash_key(),
+            Object::Boolean(false).hash_key()
+        );
+        assert_ne!(
+            Object::Integer(1).hash_key(),
+            Object::StringObj("1".to_string()).hash_key()
+        );
+
+        assert_eq!(
+            eval(r#"let h = {1: "one", true: "yes", "1": "string"}; [h[1], h[true], h["1"]]"#)
+                .to_string(),
+            "[one, yes, string]"
+        );
+    }
+
+    #[test]
+    fn test_unhashable_keys() {
+        for (input, expected) in [
+            ("[1]", "unusable as hash key: ARRAY"),
+            ("fn(x) { x }", "unusable as hash key: FUNCTION"),
+            ("{}", "unusable as hash key: HASH"),
+        ] {
+            assert_eq!(eval(input).hash_key(), Err(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_hash_equality_ignores_order() {
+        assert_eq!(eval(r#"{"z": 1, "a": 2}"#), eval(r#"{"a": 2, "z": 1}"#));
+        assert_ne!(eval(r#"{"z": 1, "a": 2}"#), eval(r#"{"a": 2, "z": 3}"#));
+        assert_ne!(eval(r#"{"z": 1}"#), eval(r#"{"z": 1, "a": 2}"#));
+    }
+
+    #[test]
+    fn test_inspect_quotes_strings() {
+        let tests = vec![
+            (r#""hi""#, r#""hi""#, "hi"),
+            (r#"["a", 1, ["b"]]"#, r#"["a", 1, ["b"]]"#, "[a, 1, [b]]"),
+            (
+                r#"{"k": "v", 2: true}"#,
+                r#"{"k": "v", 2: true}"#,
+                "{k: v, 2: true}",
+            ),
+            ("5", "5", "5"),
+            ("fn(x) { x }", "fn(x) { x }", "fn(x) { x }"),
+        ];
+
+        for (input, inspected, displayed) in tests {
+            let object = eval(input);
+            assert_eq!(object.inspect(), inspected, "input: {}", input);
+            assert_eq!(object.to_string(), displayed, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_to_json() {
+        let tests = vec![
+            ("5", r#"{"type":"INTEGER","value":5}"#),
+            ("-3", r#"{"type":"INTEGER","value":-3}"#),
+            ("true", r#"{"type":"BOOLEAN","value":true}"#),
+            (r#""hi""#, r#"{"type":"STRING","value":"hi"}"#),
+            (
+                r#"[1, "a", false]"#,
+                r#"{"type":"ARRAY","value":[{"type":"INTEGER","value":1},{"type":"STRING","value":"a"},{"type":"BOOLEAN","value":false}]}"#,
+            ),
+            (
+                r#"{"k": 1}"#,
+                r#"{"type":"HASH","value":[{"key":{"type":"STRING","value":"k"},"value":{"type":"INTEGER","value":1}}]}"#,
+            ),
+            ("fn(x) { x }", r#"{"type":"FUNCTION"}"#),
+            ("len", r#"{"type":"BUILTIN"}"#),
+            ("if (false) { 1 }", r#"{"type":"NULL","value":null}"#),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(eval(input).to_json(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_to_json_escapes_strings() {
+        let object = Object::StringObj(String::from("say \"hi\"\\\n\tok\u{1}"));
+        assert_eq!(
+            object.to_json(),
+            r#"{"type":"STRING","value":"say \"hi\"\\\n\tok\u0001"}"#
+        );
+    }
 }
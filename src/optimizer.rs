@@ -0,0 +1,411 @@
+//! Optional AST passes that run between parsing and evaluation.
+
+use crate::ast::{
+    BlockStatement, Boolean, ExpressionNode, IntegerLiteral, MatchArm, Program, StatementNode,
+};
+use crate::evaluator::is_call_to;
+use crate::token::{Position, Token, TokenKind};
+
+/// Replaces prefix and infix expressions whose operands are all integer or
+/// boolean literals with the literal they evaluate to, so `2 + 3 * 4` becomes
+/// `14`. Anything that would fail at runtime (division by zero, overflow,
+/// mismatched types) is left untouched so the evaluator reports it as usual.
+pub fn fold_constants(program: Program) -> Program {
+    ConstantFolder.rewrite_program(program)
+}
+
+/// Drops the statements that follow a `return` sitting directly in a block,
+/// since they can never run. Returns the trimmed program along with one
+/// warning per block that lost code. A `return` nested inside an `if` does not
+/// dominate the rest of its enclosing block, so nothing after the `if` is cut.
+pub fn eliminate_dead_code(program: Program) -> (Program, Vec<String>) {
+    let mut eliminator = DeadCodeEliminator {
+        warnings: Vec::new(),
+    };
+    let program = eliminator.rewrite_program(program);
+    (program, eliminator.warnings)
+}
+
+/// Rebuilds the AST bottom-up. The default methods only recurse, so a pass
+/// overrides the node kinds it cares about and calls the matching `walk_*`
+/// function to handle the children.
+pub(crate) trait Rewrite {
+    fn rewrite_program(&mut self, program: Program) -> Program {
+        Program {
+            statements: program
+                .statements
+                .into_iter()
+                .map(|stmt| self.rewrite_statement(stmt))
+                .collect(),
+        }
+    }
+
+    fn rewrite_statement(&mut self, stmt: StatementNode) -> StatementNode {
+        walk_statement(self, stmt)
+    }
+
+    fn rewrite_block(&mut self, block: BlockStatement) -> BlockStatement {
+        walk_block(self, block)
+    }
+
+    fn rewrite_expression(&mut self, expression: ExpressionNode) -> ExpressionNode {
+        walk_expression(self, expression)
+    }
+}
+
+fn walk_statement<R: Rewrite + ?Sized>(pass: &mut R, stmt: StatementNode) -> StatementNode {
+    match stmt {
+        StatementNode::Let(mut let_stmt) => {
+            let_stmt.value = pass.rewrite_expression(let_stmt.value);
+            StatementNode::Let(let_stmt)
+        }
+        StatementNode::Destructure(mut destructure) => {
+            destructure.value = pass.rewrite_expression(destructure.value);
+            StatementNode::Destructure(destructure)
+        }
+        StatementNode::Return(mut ret_stmt) => {
+            ret_stmt.return_value = pass.rewrite_expression(ret_stmt.return_value);
+            StatementNode::Return(ret_stmt)
+        }
+        StatementNode::Expression(mut exp_stmt) => {
+            exp_stmt.expression = pass.rewrite_expression(exp_stmt.expression);
+            StatementNode::Expression(exp_stmt)
+        }
+        StatementNode::Block(block) => StatementNode::Block(pass.rewrite_block(block)),
+        StatementNode::While(mut while_stmt) => {
+            while_stmt.condition = pass.rewrite_expression(while_stmt.condition);
+            while_stmt.body = pass.rewrite_block(while_stmt.body);
+            StatementNode::While(while_stmt)
+        }
+        StatementNode::Break(_) | StatementNode::Continue(_) => stmt,
+    }
+}
+
+fn walk_block<R: Rewrite + ?Sized>(pass: &mut R, mut block: BlockStatement) -> BlockStatement {
+    block.statements = block
+        .statements
+        .into_iter()
+        .map(|stmt| pass.rewrite_statement(stmt))
+        .collect();
+    block
+}
+
+pub(crate) fn walk_expression<R: Rewrite + ?Sized>(
+    pass: &mut R,
+    expression: ExpressionNode,
+) -> ExpressionNode {
+    match expression {
+        ExpressionNode::Prefix(mut prefix_exp) => {
+            prefix_exp.right = Box::new(pass.rewrite_expression(*prefix_exp.right));
+            ExpressionNode::Prefix(prefix_exp)
+        }
+        ExpressionNode::Infix(mut inf_exp) => {
+            inf_exp.left = Box::new(pass.rewrite_expression(*inf_exp.left));
+            inf_exp.right = Box::new(pass.rewrite_expression(*inf_exp.right));
+            ExpressionNode::Infix(inf_exp)
+        }
+        ExpressionNode::IfExpressionNode(mut if_exp) => {
+            if_exp.condition = Box::new(pass.rewrite_expression(*if_exp.condition));
+            if_exp.consequence = pass.rewrite_block(if_exp.consequence);
+            if_exp.alternative = if_exp.alternative.map(|alt| pass.rewrite_block(alt));
+            ExpressionNode::IfExpressionNode(if_exp)
+        }
+        ExpressionNode::Function(mut fn_lit) => {
+            fn_lit.body = pass.rewrite_block(fn_lit.body);
+            ExpressionNode::Function(fn_lit)
+        }
+        ExpressionNode::Macro(mut macro_lit) => {
+            macro_lit.body = pass.rewrite_block(macro_lit.body);
+            ExpressionNode::Macro(macro_lit)
+        }
+        ExpressionNode::BlockExp(block) => ExpressionNode::BlockExp(pass.rewrite_block(block)),
+        ExpressionNode::Match(mut match_exp) => {
+            match_exp.subject = Box::new(pass.rewrite_expression(*match_exp.subject));
+            match_exp.arms = match_exp
+                .arms
+                .into_iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.map(|pattern| pass.rewrite_expression(pattern)),
+                    value: pass.rewrite_expression(arm.value),
+                })
+                .collect();
+            ExpressionNode::Match(match_exp)
+        }
+        ExpressionNode::Assign(mut assign) => {
+            assign.value = Box::new(pass.rewrite_expression(*assign.value));
+            ExpressionNode::Assign(assign)
+        }
+        ExpressionNode::Call(mut call_exp) => {
+            call_exp.function = Box::new(pass.rewrite_expression(*call_exp.function));
+            call_exp.arguments = call_exp
+                .arguments
+                .into_iter()
+                .map(|arg| pass.rewrite_expression(arg))
+                .collect();
+            ExpressionNode::Call(call_exp)
+        }
+        ExpressionNode::Array(mut array_literal) => {
+            array_literal.elements = array_literal
+                .elements
+                .into_iter()
+                .map(|element| pass.rewrite_expression(element))
+                .collect();
+            ExpressionNode::Array(array_literal)
+        }
+        ExpressionNode::Index(mut index_exp) => {
+            index_exp.left = Box::new(pass.rewrite_expression(*index_exp.left));
+            index_exp.index = Box::new(pass.rewrite_expression(*index_exp.index));
+            ExpressionNode::Index(index_exp)
+        }
+        ExpressionNode::Hash(mut hash_literal) => {
+            hash_literal.pairs = hash_literal
+                .pairs
+                .into_iter()
+                .map(|(key, value)| (pass.rewrite_expression(key), pass.rewrite_expression(value)))
+                .collect();
+            ExpressionNode::Hash(hash_literal)
+        }
+        ExpressionNode::IdentifierNode(_)
+        | ExpressionNode::Integer(_)
+        | ExpressionNode::Float(_)
+        | ExpressionNode::BooleanNode(_)
+        | ExpressionNode::StringExp(_)
+        | ExpressionNode::Char(_)
+        | ExpressionNode::None => expression,
+    }
+}
+
+struct ConstantFolder;
+
+impl Rewrite for ConstantFolder {
+    fn rewrite_expression(&mut self, expression: ExpressionNode) -> ExpressionNode {
+        // a quoted expression is data; folding it would change its value
+        if let ExpressionNode::Call(call_exp) = &expression {
+            if is_call_to(call_exp, "quote") {
+                return expression;
+            }
+        }
+        // children first, so `(1 + 2) * 3` sees a literal on the left
+        let expression = walk_expression(self, expression);
+        let folded = match &expression {
+            ExpressionNode::Prefix(prefix_exp) => {
+                let position = prefix_exp.token.position;
+                match (prefix_exp.operator.as_str(), prefix_exp.right.as_ref()) {
+                    ("-", ExpressionNode::Integer(int)) => int
+                        .value
+                        .checked_neg()
+                        .map(|value| integer_node(value, position)),
+                    ("+", ExpressionNode::Integer(int)) => Some(integer_node(int.value, position)),
+                    ("!", ExpressionNode::BooleanNode(boolean)) => {
+                        Some(boolean_node(!boolean.value, position))
+                    }
+                    _ => None,
+                }
+            }
+            ExpressionNode::Infix(inf_exp) => {
+                let position = inf_exp.token.position;
+                match (inf_exp.left.as_ref(), inf_exp.right.as_ref()) {
+                    (ExpressionNode::Integer(l), ExpressionNode::Integer(r)) => {
+                        fold_integer_infix(&inf_exp.operator, l.value, r.value, position)
+                    }
+                    (ExpressionNode::BooleanNode(l), ExpressionNode::BooleanNode(r)) => {
+                        match inf_exp.operator.as_str() {
+                            "==" => Some(boolean_node(l.value == r.value, position)),
+                            "!=" => Some(boolean_node(l.value != r.value, position)),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        folded.unwrap_or(expression)
+    }
+}
+
+fn fold_integer_infix(
+    operator: &str,
+    left: i64,
+    right: i64,
+    position: Position,
+) -> Option<ExpressionNode> {
+    let value = match operator {
+        "+" => left.checked_add(right)?,
+        "-" => left.checked_sub(right)?,
+        "*" => left.checked_mul(right)?,
+        // `checked_div` is also `None` for a zero divisor. When the quotient
+        // is inexact and negative it depends on the evaluator's `DivMode`.
+        "/" if left.checked_rem(right)? != 0 && (left < 0) != (right < 0) => return None,
+        "/" => left.checked_div(right)?,
+        "<" => return Some(boolean_node(left < right, position)),
+        ">" => return Some(boolean_node(left > right, position)),
+        "==" => return Some(boolean_node(left == right, position)),
+        "!=" => return Some(boolean_node(left != right, position)),
+        _ => return None,
+    };
+    Some(integer_node(value, position))
+}
+
+pub(crate) fn integer_node(value: i64, position: Position) -> ExpressionNode {
+    ExpressionNode::Integer(IntegerLiteral {
+        token: Token {
+            kind: TokenKind::Int,
+            literal: value.to_string().into(),
+            position,
+            ..Default::default()
+        },
+        value,
+    })
+}
+
+pub(crate) fn boolean_node(value: bool, position: Position) -> ExpressionNode {
+    let kind = if value {
+        TokenKind::True
+    } else {
+        TokenKind::False
+    };
+    ExpressionNode::BooleanNode(Boolean {
+        token: Token {
+            kind,
+            literal: value.to_string().into(),
+            position,
+            ..Default::default()
+        },
+        value,
+    })
+}
+
+struct DeadCodeEliminator {
+    warnings: Vec<String>,
+}
+
+impl Rewrite for DeadCodeEliminator {
+    fn rewrite_block(&mut self, block: BlockStatement) -> BlockStatement {
+        let mut block = walk_block(self, block);
+
+        let first_return = block
+            .statements
+            .iter()
+            .position(|stmt| matches!(stmt, StatementNode::Return(_)));
+        if let Some(idx) = first_return {
+            let removed = block.statements.len() - idx - 1;
+            if removed > 0 {
+                if let StatementNode::Return(ret_stmt) = &block.statements[idx] {
+                    self.warnings.push(format!(
+                        "unreachable code after return at line {}, col {}: {} statement(s) removed",
+                        ret_stmt.token.position.line, ret_stmt.token.position.column, removed
+                    ));
+                }
+                block.statements.truncate(idx + 1);
+            }
+        }
+        block
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{eliminate_dead_code, fold_constants};
+    use crate::ast::{ExpressionNode, StatementNode};
+    use crate::{evaluator::Evaluator, lexer::Lexer, object::Object, parser::Parser};
+
+    fn parse(input: &str) -> crate::ast::Program {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "parser errors: {:?}",
+            parser.errors()
+        );
+        program
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        let tests = vec![
+            ("2 + 3 * 4", "14"),
+            ("(5 + 10 * 2 + 15 / 3) * 2 + -10", "50"),
+            ("-(1 + 2)", "-3"),
+            ("+(1 + 2)", "3"),
+            ("+true", "(+true)"),
+            ("1 < 2", "true"),
+            ("!(1 == 2)", "true"),
+            ("true != false", "true"),
+            ("x + 2 * 3", "(x + 6)"),
+            ("10 / 0", "(10 / 0)"),
+            ("1 / (2 - 2)", "(1 / 0)"),
+            ("-8 / 2", "-4"),
+            ("-7 / 2", "(-7 / 2)"),
+            ("7 % 2", "(7 % 2)"),
+            ("9223372036854775807 + 1", "(9223372036854775807 + 1)"),
+            ("1 + true", "(1 + true)"),
+            ("let y = 2 * 21;", "let y = 42;"),
+            ("fn(a) { a * (2 + 2) }", "fn(a) (a * 4)"),
+            ("if (1 > 2) { 1 + 1 } else { [3 * 3] }", "iffalse 2else [9]"),
+            ("f(1 + 1)[2 - 2]", "(f(2)[0])"),
+            ("while (1 < 0) { 2 * 2 }", "whilefalse 4"),
+            ("quote(1 + 1) + (1 + 1)", "(quote((1 + 1)) + 2)"),
+        ];
+
+        for (input, expected) in tests {
+            let folded = fold_constants(parse(input));
+            assert_eq!(folded.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_folding_preserves_results() {
+        let input = "let f = fn(n) { n * (3 - 1) + 10 / 5 }; f(4) + (2 * 3);";
+
+        let plain = Evaluator::new().eval_program(parse(input));
+        let folded = Evaluator::new().eval_program(fold_constants(parse(input)));
+        assert_eq!(plain, folded);
+        assert_eq!(folded, Object::Integer(16));
+    }
+
+    #[test]
+    fn test_eliminate_dead_code_after_return() {
+        let (program, warnings) = eliminate_dead_code(parse("fn() { return 1; 2; 3; }"));
+
+        match &program.statements[0] {
+            StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
+                ExpressionNode::Function(fn_lit) => {
+                    assert_eq!(fn_lit.body.statements.len(), 1);
+                    assert_eq!(fn_lit.body.to_string(), "return 1;");
+                }
+                other => panic!("expression is not a function. got={:?}", other),
+            },
+            other => panic!("statement is not an expression. got={:?}", other),
+        }
+        assert_eq!(
+            warnings,
+            vec!["unreachable code after return at line 1, col 8: 2 statement(s) removed"]
+        );
+    }
+
+    #[test]
+    fn test_eliminate_dead_code_keeps_reachable_code() {
+        let tests = vec![
+            // the `return` only runs on one branch, so the code after the `if` stays
+            (
+                "fn(x) { if (x) { return 1; 5; } 2; }",
+                "fn(x) ifx return 1;2",
+                1,
+            ),
+            ("fn() { 1; return 2; }", "fn() 1return 2;", 0),
+            (
+                "let f = fn() { fn() { return 1; 2 } };",
+                "let f = fn() fn() return 1;;",
+                1,
+            ),
+        ];
+
+        for (input, expected, warning_count) in tests {
+            let (program, warnings) = eliminate_dead_code(parse(input));
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+            assert_eq!(warnings.len(), warning_count, "input: {}", input);
+        }
+    }
+}
@@ -1,51 +1,90 @@
 use std::collections::HashMap;
+use std::mem;
+use std::rc::Rc;
 
 use crate::ast::{
-    ArrayLiteral, BlockStatement, Boolean, CallExpression, ExpressionNode, ExpressionStatement,
-    FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression, InfixExpression,
-    IntegerLiteral, LetStatement, PrefixExpression, Program, ReturnStatement, StatementNode,
-    StringLiteral,
+    ArrayLiteral, AssignExpression, BlockStatement, Boolean, BreakStatement, CallExpression,
+    CharLiteral, ContinueStatement, DestructureStatement, ExpressionNode, ExpressionStatement,
+    FloatLiteral, FunctionLiteral, HashLiteral, Identifier, IfExpression, IndexExpression,
+    InfixExpression, IntegerLiteral, LetStatement, MacroLiteral, MatchArm, MatchExpression,
+    PrefixExpression, Program, ReturnStatement, StatementNode, StringLiteral, WhileStatement,
 };
 use crate::lexer::Lexer;
 use crate::token::{Token, TokenKind};
 
-type PrefixParseFn = fn(&mut Parser) -> ExpressionNode;
-type InfixParseFn = fn(&mut Parser, ExpressionNode) -> ExpressionNode;
+/// Parses an expression that starts with `cur_token`. It is called with
+/// `cur_token` on the token it was registered for and must leave `cur_token`
+/// on the expression's last token.
+pub type PrefixParseFn<'a> = fn(&mut Parser<'a>) -> ExpressionNode;
 
+/// Parses the rest of an expression whose left operand has already been
+/// parsed. It is called with `cur_token` on the operand's last token and
+/// `peek_token` on the operator it was registered for, and must leave
+/// `cur_token` on the expression's last token.
+pub type InfixParseFn<'a> = fn(&mut Parser<'a>, ExpressionNode) -> ExpressionNode;
+
+/// Type names accepted in `let x: <type> = ...` annotations.
+const KNOWN_TYPES: [&str; 3] = ["int", "bool", "string"];
+
+/// How tightly an infix operator binds; higher levels bind tighter.
 #[derive(Debug, Copy, Clone)]
-enum PrecedenceLevel {
+pub enum PrecedenceLevel {
     Lowest = 0,
-    Equals = 1,      // ==
-    LessGreater = 2, // > or <
-    Sum = 3,         // +
-    Product = 4,
-    Prefix = 5,
-    Call = 6,
-    Index = 7,
+    Assign = 1,      // =
+    Pipe = 2,        // |>
+    Equals = 3,      // ==
+    LessGreater = 4, // > or <
+    Sum = 5,         // +
+    Product = 6,
+    Prefix = 7,
+    Call = 8,
+    Index = 9,
 }
-fn precedence_map(token_kind: &TokenKind) -> PrecedenceLevel {
-    match token_kind {
-        TokenKind::EQ | TokenKind::NotEQ => PrecedenceLevel::Equals,
-        TokenKind::LT | TokenKind::GT => PrecedenceLevel::LessGreater,
-        TokenKind::Plus | TokenKind::Minus => PrecedenceLevel::Sum,
-        TokenKind::Slash | TokenKind::Asterisk => PrecedenceLevel::Product,
-        TokenKind::LParen => PrecedenceLevel::Call,
-        TokenKind::LBracket => PrecedenceLevel::Index,
-        _ => PrecedenceLevel::Lowest,
-    }
+
+/// The precedence of every infix token. Tokens missing from the table are
+/// treated as `Lowest`, which ends an expression.
+const DEFAULT_PRECEDENCES: [(TokenKind, PrecedenceLevel); 14] = [
+    (TokenKind::Assign, PrecedenceLevel::Assign),
+    (TokenKind::Pipe, PrecedenceLevel::Pipe),
+    (TokenKind::EQ, PrecedenceLevel::Equals),
+    (TokenKind::NotEQ, PrecedenceLevel::Equals),
+    (TokenKind::LT, PrecedenceLevel::LessGreater),
+    (TokenKind::GT, PrecedenceLevel::LessGreater),
+    (TokenKind::Plus, PrecedenceLevel::Sum),
+    (TokenKind::Minus, PrecedenceLevel::Sum),
+    (TokenKind::Slash, PrecedenceLevel::Product),
+    (TokenKind::Asterisk, PrecedenceLevel::Product),
+    (TokenKind::Percent, PrecedenceLevel::Product),
+    (TokenKind::LParen, PrecedenceLevel::Call),
+    (TokenKind::Dot, PrecedenceLevel::Call),
+    (TokenKind::LBracket, PrecedenceLevel::Index),
+];
+
+fn default_precedences() -> HashMap<TokenKind, PrecedenceLevel> {
+    HashMap::from(DEFAULT_PRECEDENCES)
 }
 
-pub struct Parser {
-    lexer: Lexer,
+/// The precedence `kind` has in a parser whose table was never changed with
+/// [`Parser::set_precedence`].
+pub(crate) fn default_precedence(kind: &TokenKind) -> PrecedenceLevel {
+    DEFAULT_PRECEDENCES
+        .iter()
+        .find(|(token, _)| token == kind)
+        .map_or(PrecedenceLevel::Lowest, |(_, level)| *level)
+}
+
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
     pub cur_token: Token,
     pub peek_token: Token,
     errors: Vec<String>,
-    prefix_parse_fns: HashMap<TokenKind, PrefixParseFn>,
-    infix_parse_fns: HashMap<TokenKind, InfixParseFn>,
+    prefix_parse_fns: HashMap<TokenKind, PrefixParseFn<'a>>,
+    infix_parse_fns: HashMap<TokenKind, InfixParseFn<'a>>,
+    precedences: HashMap<TokenKind, PrecedenceLevel>,
 }
 
-impl Parser {
-    pub fn new(lexer: Lexer) -> Parser {
+impl<'a> Parser<'a> {
+    pub fn new(lexer: Lexer<'a>) -> Parser<'a> {
         let mut parser = Parser {
             lexer,
             cur_token: Default::default(),
@@ -53,33 +92,44 @@ impl Parser {
             errors: Vec::new(),
             prefix_parse_fns: HashMap::new(),
             infix_parse_fns: HashMap::new(),
+            precedences: default_precedences(),
         };
 
         //PREFIX
         parser.register_prefix(TokenKind::Ident, Self::parse_identifier);
         parser.register_prefix(TokenKind::Int, Self::parse_integer_literal);
+        parser.register_prefix(TokenKind::Float, Self::parse_float_literal);
         parser.register_prefix(TokenKind::Bang, Self::parse_prefix_expression);
         parser.register_prefix(TokenKind::Minus, Self::parse_prefix_expression);
+        parser.register_prefix(TokenKind::Plus, Self::parse_prefix_expression);
         parser.register_prefix(TokenKind::True, Self::parse_boolean);
         parser.register_prefix(TokenKind::False, Self::parse_boolean);
         parser.register_prefix(TokenKind::LParen, Self::parse_grouped_expression);
         parser.register_prefix(TokenKind::If, Self::parse_if_expression);
+        parser.register_prefix(TokenKind::Match, Self::parse_match_expression);
         parser.register_prefix(TokenKind::Function, Self::parse_function_literal);
+        parser.register_prefix(TokenKind::Macro, Self::parse_macro_literal);
         parser.register_prefix(TokenKind::String, Self::parse_string_literal);
+        parser.register_prefix(TokenKind::RawString, Self::parse_string_literal);
+        parser.register_prefix(TokenKind::Char, Self::parse_char_literal);
         parser.register_prefix(TokenKind::LBracket, Self::parse_array_literal);
-        parser.register_prefix(TokenKind::LBrace, Self::parse_hash_literal);
+        parser.register_prefix(TokenKind::LBrace, Self::parse_brace_expression);
 
         //INFIX
         parser.register_infix(TokenKind::Plus, Self::parse_infix_expression);
         parser.register_infix(TokenKind::Minus, Self::parse_infix_expression);
         parser.register_infix(TokenKind::Slash, Self::parse_infix_expression);
         parser.register_infix(TokenKind::Asterisk, Self::parse_infix_expression);
+        parser.register_infix(TokenKind::Percent, Self::parse_infix_expression);
         parser.register_infix(TokenKind::EQ, Self::parse_infix_expression);
         parser.register_infix(TokenKind::NotEQ, Self::parse_infix_expression);
         parser.register_infix(TokenKind::LT, Self::parse_infix_expression);
         parser.register_infix(TokenKind::GT, Self::parse_infix_expression);
         parser.register_infix(TokenKind::LParen, Self::parse_call_expression);
+        parser.register_infix(TokenKind::Dot, Self::parse_method_call_expression);
         parser.register_infix(TokenKind::LBracket, Self::parse_index_expression);
+        parser.register_infix(TokenKind::Assign, Self::parse_assign_expression);
+        parser.register_infix(TokenKind::Pipe, Self::parse_pipe_expression);
 
         parser.next_token();
         parser.next_token();
@@ -87,9 +137,18 @@ impl Parser {
         parser
     }
 
-    fn next_token(&mut self) {
-        self.cur_token = self.peek_token.clone();
-        self.peek_token = self.lexer.next_token();
+    /// Advances `cur_token` and `peek_token` by one token.
+    pub fn next_token(&mut self) {
+        self.cur_token = mem::replace(&mut self.peek_token, self.lexer.next_token());
+    }
+
+    /// Literals are shared `Rc<str>`s, so this only bumps reference counts;
+    /// repeated names keep pointing at the lexer's interned copy.
+    fn cur_identifier(&self) -> Identifier {
+        Identifier {
+            token: self.cur_token.clone(),
+            value: Rc::clone(&self.cur_token.literal),
+        }
     }
 
     pub fn parse_program(&mut self) -> Program {
@@ -156,26 +215,124 @@ impl Parser {
 
     fn parse_statement(&mut self) -> Option<StatementNode> {
         match self.cur_token.kind {
-            TokenKind::Let => self.parse_let_statement(),
+            TokenKind::Let | TokenKind::Const => self.parse_let_statement(),
             TokenKind::Return => self.parse_return_statement(),
+            TokenKind::While => self.parse_while_statement(),
+            TokenKind::Break => {
+                let token = self.cur_token.clone();
+                self.skip_optional_semicolon();
+                Some(StatementNode::Break(BreakStatement { token }))
+            }
+            TokenKind::Continue => {
+                let token = self.cur_token.clone();
+                self.skip_optional_semicolon();
+                Some(StatementNode::Continue(ContinueStatement { token }))
+            }
+            TokenKind::Function if self.peek_token_is(&TokenKind::Ident) => {
+                self.parse_function_statement()
+            }
             _ => self.parse_expression_statement(),
         }
     }
 
+    fn skip_optional_semicolon(&mut self) {
+        if self.peek_token_is(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+    }
+
+    fn parse_while_statement(&mut self) -> Option<StatementNode> {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::LParen) {
+            return None;
+        }
+
+        self.next_token();
+
+        let condition = self.parse_expression(PrecedenceLevel::Lowest);
+
+        if !self.expect_peek(TokenKind::RParen) {
+            return None;
+        }
+
+        if !self.expect_peek(TokenKind::LBrace) {
+            return None;
+        }
+
+        let body = self.parse_block_statement();
+
+        Some(StatementNode::While(WhileStatement {
+            token,
+            condition,
+            body,
+        }))
+    }
+
+    /// `fn name(params) { body }` is sugar for `let name = fn(params) { body };`.
+    fn parse_function_statement(&mut self) -> Option<StatementNode> {
+        let fn_token = self.cur_token.clone();
+        let position = fn_token.position;
+        self.next_token();
+
+        let name = self.cur_identifier();
+
+        // parse_function_literal picks up at the parameter list after the name
+        let value = match self.parse_function_literal() {
+            ExpressionNode::Function(mut func_lit) => {
+                func_lit.token = fn_token;
+                ExpressionNode::Function(func_lit)
+            }
+            _ => return None,
+        };
+
+        if self.peek_token_is(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(StatementNode::Let(LetStatement {
+            token: Token {
+                kind: TokenKind::Let,
+                literal: "let".into(),
+                position,
+                ..Default::default()
+            },
+            name,
+            type_annotation: None,
+            value,
+        }))
+    }
+
     fn parse_let_statement(&mut self) -> Option<StatementNode> {
+        if self.peek_token_is(&TokenKind::LBracket) {
+            return self.parse_destructure_statement();
+        }
+
         let mut stmt = LetStatement {
             token: self.cur_token.clone(),
             name: Default::default(),
+            type_annotation: None,
             value: Default::default(),
         };
 
         if !self.expect_peek(TokenKind::Ident) {
             None
         } else {
-            stmt.name = Identifier {
-                token: self.cur_token.clone(),
-                value: self.cur_token.literal.clone(),
-            };
+            stmt.name = self.cur_identifier();
+
+            if self.peek_token_is(&TokenKind::Colon) {
+                self.next_token();
+                if !self.expect_peek(TokenKind::Ident) {
+                    return None;
+                }
+                stmt.type_annotation = Some(self.parse_type_annotation(&stmt.name));
+            }
+
+            // `let x;` declares `x` without a value; it starts out `null`
+            if self.peek_token_is(&TokenKind::Semicolon) && !stmt.is_constant() {
+                self.next_token();
+                return Some(StatementNode::Let(stmt));
+            }
 
             if !self.expect_peek(TokenKind::Assign) {
                 None
@@ -190,6 +347,50 @@ impl Parser {
         }
     }
 
+    /// `let [a, b, ...rest] = value;`, with `cur_token` on the `let`.
+    fn parse_destructure_statement(&mut self) -> Option<StatementNode> {
+        let token = self.cur_token.clone();
+        self.next_token();
+
+        let (mut names, has_rest) = self.parse_binding_list(TokenKind::RBracket)?;
+        let rest = if has_rest { names.pop() } else { None };
+
+        if !self.expect_peek(TokenKind::Assign) {
+            return None;
+        }
+        self.next_token();
+        let value = self.parse_expression(PrecedenceLevel::Lowest);
+        if self.peek_token_is(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+
+        Some(StatementNode::Destructure(DestructureStatement {
+            token,
+            names,
+            rest,
+            value,
+        }))
+    }
+
+    /// Reads the type name at `cur_token`. Unknown names are reported in
+    /// `errors()` but still attached, since annotations are not enforced yet.
+    fn parse_type_annotation(&mut self, name: &Identifier) -> Identifier {
+        let annotation = self.cur_identifier();
+
+        if !KNOWN_TYPES.contains(&&*annotation.value) {
+            self.errors.push(format!(
+                "unknown type annotation '{}' for '{}', expected one of: {}",
+                annotation.value,
+                name.value,
+                KNOWN_TYPES.join(", ")
+            ));
+        }
+        annotation
+    }
+
+    /// A statement that is nothing but a `{ ... }` block becomes a
+    /// `StatementNode::Block`; a block used inside a larger expression, such as
+    /// `{ x } + 1`, stays an expression.
     fn parse_expression_statement(&mut self) -> Option<StatementNode> {
         let stmt = ExpressionStatement {
             token: self.cur_token.clone(),
@@ -198,24 +399,39 @@ impl Parser {
         if self.peek_token_is(&TokenKind::Semicolon) {
             self.next_token();
         }
-        Some(StatementNode::Expression(stmt))
+        match stmt.expression {
+            ExpressionNode::BlockExp(block) => Some(StatementNode::Block(block)),
+            _ => Some(StatementNode::Expression(stmt)),
+        }
     }
 
-    fn parse_expression(&mut self, precedence_level: PrecedenceLevel) -> ExpressionNode {
+    /// Parses the expression starting at `cur_token`, consuming infix operators
+    /// that bind tighter than `precedence_level`.
+    pub fn parse_expression(&mut self, precedence_level: PrecedenceLevel) -> ExpressionNode {
         let prefix = self.prefix_parse_fns.get(&self.cur_token.kind);
         if let Some(prefix_fn) = prefix {
             let mut left_exp = prefix_fn(self);
             while !self.peek_token_is(&TokenKind::Semicolon)
                 && (precedence_level as u8) < (self.peek_precedence() as u8)
             {
-                let infix_fn = self.infix_parse_fns.get(&self.peek_token.kind);
-                if let Some(infix_func) = infix_fn {
-                    left_exp = infix_func(self, left_exp);
+                // a token can be given a precedence without a parse function
+                match self.infix_parse_fns.get(&self.peek_token.kind) {
+                    Some(infix_func) => left_exp = infix_func(self, left_exp),
+                    None => break,
                 }
             }
             return left_exp;
         };
-        self.no_prefix_parse_fn_error(self.cur_token.kind.clone());
+        if self.cur_token_is(TokenKind::Illegal) {
+            let msg = format!(
+                "{} at {}",
+                self.cur_token.illegal_message(),
+                self.cur_token.position
+            );
+            self.errors.push(msg);
+        } else {
+            self.no_prefix_parse_fn_error(self.cur_token.kind.clone());
+        }
         ExpressionNode::None
     }
 
@@ -225,9 +441,41 @@ impl Parser {
     }
 
     fn parse_identifier(&mut self) -> ExpressionNode {
-        ExpressionNode::IdentifierNode(Identifier {
-            token: self.cur_token.clone(),
-            value: self.cur_token.literal.clone(),
+        let identifier = self.cur_identifier();
+
+        if self.peek_token_is(&TokenKind::Arrow) {
+            self.next_token();
+            return self.parse_arrow_function(vec![identifier]);
+        }
+
+        ExpressionNode::IdentifierNode(identifier)
+    }
+
+    /// Parses the body of `x -> body` or `(x, y) -> body`, with `cur_token` on
+    /// the `->`, into the function literal `fn(x, y) { return body; }`.
+    fn parse_arrow_function(&mut self, parameters: Vec<Identifier>) -> ExpressionNode {
+        let arrow = self.cur_token.clone();
+        let keyword = |kind: TokenKind, literal: &str| Token {
+            kind,
+            literal: literal.into(),
+            position: arrow.position,
+            ..Default::default()
+        };
+
+        self.next_token();
+        let body = ReturnStatement {
+            token: keyword(TokenKind::Return, "return"),
+            return_value: self.parse_expression(PrecedenceLevel::Lowest),
+        };
+
+        ExpressionNode::Function(FunctionLiteral {
+            token: keyword(TokenKind::Function, "fn"),
+            parameters,
+            body: BlockStatement {
+                token: arrow.clone(),
+                statements: vec![StatementNode::Return(body)],
+            },
+            variadic: false,
         })
     }
 
@@ -237,7 +485,16 @@ impl Parser {
             value: Default::default(),
         };
 
-        match self.cur_token.literal.parse::<i64>() {
+        // `0123` is more likely a typo or an intended octal than decimal 123
+        if literal.token.literal.len() > 1 && literal.token.literal.starts_with('0') {
+            self.errors.push(format!(
+                "integer literal '{}' must not have leading zeros",
+                literal.token.literal
+            ));
+            return ExpressionNode::None;
+        }
+
+        match literal.token.literal.parse::<i64>() {
             Ok(value) => {
                 literal.value = value;
                 ExpressionNode::Integer(literal)
@@ -245,17 +502,31 @@ impl Parser {
             Err(_) => {
                 self.errors.push(format!(
                     "could not parse '{}' as integer",
-                    self.cur_token.literal
+                    literal.token.literal
                 ));
                 ExpressionNode::None
             }
         }
     }
 
+    fn parse_float_literal(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
+
+        match token.literal.parse::<f64>() {
+            Ok(value) => ExpressionNode::Float(FloatLiteral { token, value }),
+            Err(_) => {
+                self.errors
+                    .push(format!("could not parse '{}' as float", token.literal));
+                ExpressionNode::None
+            }
+        }
+    }
+
     fn parse_prefix_expression(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
         let mut expression = PrefixExpression {
-            token: self.cur_token.clone(),
-            operator: self.cur_token.literal.clone(),
+            operator: token.literal.to_string(),
+            token,
             right: Default::default(),
         };
 
@@ -268,9 +539,10 @@ impl Parser {
     fn parse_infix_expression(&mut self, left: ExpressionNode) -> ExpressionNode {
         self.next_token();
 
+        let token = self.cur_token.clone();
         let mut expression = InfixExpression {
-            token: self.cur_token.clone(),
-            operator: self.cur_token.literal.clone(),
+            operator: token.literal.to_string(),
+            token,
             left: Box::new(left),
             right: Default::default(),
         };
@@ -281,20 +553,64 @@ impl Parser {
         ExpressionNode::Infix(expression)
     }
 
-    fn register_prefix(&mut self, token_kind: TokenKind, func: PrefixParseFn) {
+    /// Assignment is right-associative, so `a = b = 1` sets both names. Only
+    /// a plain identifier can be assigned to.
+    fn parse_assign_expression(&mut self, left: ExpressionNode) -> ExpressionNode {
+        self.next_token();
+        let token = self.cur_token.clone();
+
+        self.next_token();
+        let value = self.parse_expression(PrecedenceLevel::Lowest);
+
+        let name = match left {
+            ExpressionNode::IdentifierNode(identifier) => identifier,
+            other => {
+                self.errors
+                    .push(format!("invalid assignment target: {}", other));
+                return ExpressionNode::None;
+            }
+        };
+
+        ExpressionNode::Assign(AssignExpression {
+            token,
+            name,
+            value: Box::new(value),
+        })
+    }
+
+    /// Parses expressions starting with `token_kind` using `func`, replacing
+    /// any existing rule for that token.
+    pub fn register_prefix(&mut self, token_kind: TokenKind, func: PrefixParseFn<'a>) {
         self.prefix_parse_fns.insert(token_kind, func);
     }
 
-    fn register_infix(&mut self, token_kind: TokenKind, func: InfixParseFn) {
+    /// Parses `token_kind` as an infix operator using `func`, replacing any
+    /// existing rule. The operator also needs a precedence above `Lowest`,
+    /// see [`Parser::set_precedence`].
+    pub fn register_infix(&mut self, token_kind: TokenKind, func: InfixParseFn<'a>) {
         self.infix_parse_fns.insert(token_kind, func);
     }
 
+    /// Overrides how tightly the infix operator `token_kind` binds, e.g. to
+    /// experiment with operator precedence. Takes effect for everything parsed
+    /// afterwards.
+    pub fn set_precedence(&mut self, token_kind: TokenKind, level: PrecedenceLevel) {
+        self.precedences.insert(token_kind, level);
+    }
+
+    fn precedence_of(&self, token_kind: &TokenKind) -> PrecedenceLevel {
+        self.precedences
+            .get(token_kind)
+            .copied()
+            .unwrap_or(PrecedenceLevel::Lowest)
+    }
+
     fn peek_precedence(&self) -> PrecedenceLevel {
-        precedence_map(&self.peek_token.kind)
+        self.precedence_of(&self.peek_token.kind)
     }
 
     fn cur_precedence(&self) -> PrecedenceLevel {
-        precedence_map(&self.cur_token.kind)
+        self.precedence_of(&self.cur_token.kind)
     }
 
     fn parse_boolean(&mut self) -> ExpressionNode {
@@ -304,16 +620,46 @@ impl Parser {
         })
     }
 
+    /// Parses `(exp)`, or the parameter list of an arrow function: `()`,
+    /// `(x) ->` or `(x, y) ->`.
     fn parse_grouped_expression(&mut self) -> ExpressionNode {
-        self.next_token();
+        let mut expressions = Vec::new();
 
-        let exp = self.parse_expression(PrecedenceLevel::Lowest);
+        if !self.peek_token_is(&TokenKind::RParen) {
+            self.next_token();
+            expressions.push(self.parse_expression(PrecedenceLevel::Lowest));
+            while self.peek_token_is(&TokenKind::Comma) {
+                self.next_token();
+                self.next_token();
+                expressions.push(self.parse_expression(PrecedenceLevel::Lowest));
+            }
+        }
 
         if !self.expect_peek(TokenKind::RParen) {
             return ExpressionNode::None;
         }
 
-        exp
+        if expressions.len() == 1 && !self.peek_token_is(&TokenKind::Arrow) {
+            return expressions.remove(0);
+        }
+        if !self.expect_peek(TokenKind::Arrow) {
+            return ExpressionNode::None;
+        }
+
+        let mut parameters = Vec::with_capacity(expressions.len());
+        for expression in expressions {
+            match expression {
+                ExpressionNode::IdentifierNode(identifier) => parameters.push(identifier),
+                other => {
+                    self.errors.push(format!(
+                        "arrow function parameters must be names, got {}",
+                        other
+                    ));
+                    return ExpressionNode::None;
+                }
+            }
+        }
+        self.parse_arrow_function(parameters)
     }
 
     fn parse_if_expression(&mut self) -> ExpressionNode {
@@ -355,6 +701,53 @@ impl Parser {
         ExpressionNode::IfExpressionNode(expression)
     }
 
+    /// `match (subject) { pattern => value, ... }`. Arms are separated by
+    /// commas, with an optional trailing one; a bare `_` pattern matches
+    /// anything.
+    fn parse_match_expression(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::LParen) {
+            return ExpressionNode::None;
+        }
+        self.next_token();
+        let subject = self.parse_expression(PrecedenceLevel::Lowest);
+        if !self.expect_peek(TokenKind::RParen) || !self.expect_peek(TokenKind::LBrace) {
+            return ExpressionNode::None;
+        }
+
+        let mut arms = Vec::new();
+        while !self.peek_token_is(&TokenKind::RBrace) {
+            self.next_token();
+            let pattern = if &*self.cur_token.literal == "_"
+                && self.cur_token_is(TokenKind::Ident)
+                && self.peek_token_is(&TokenKind::FatArrow)
+            {
+                None
+            } else {
+                Some(self.parse_expression(PrecedenceLevel::Lowest))
+            };
+
+            if !self.expect_peek(TokenKind::FatArrow) {
+                return ExpressionNode::None;
+            }
+            self.next_token();
+            let value = self.parse_expression(PrecedenceLevel::Lowest);
+            arms.push(MatchArm { pattern, value });
+
+            if !self.peek_token_is(&TokenKind::RBrace) && !self.expect_peek(TokenKind::Comma) {
+                return ExpressionNode::None;
+            }
+        }
+        self.next_token(); // consume the }
+
+        ExpressionNode::Match(MatchExpression {
+            token,
+            subject: Box::new(subject),
+            arms,
+        })
+    }
+
     fn parse_block_statement(&mut self) -> BlockStatement {
         let mut block = BlockStatement {
             token: self.cur_token.clone(),
@@ -362,30 +755,39 @@ impl Parser {
         };
 
         self.next_token();
+        self.parse_block_rest(&mut block);
+
+        block
+    }
 
+    /// Appends statements to `block` from the current token up to the closing `}`.
+    fn parse_block_rest(&mut self, block: &mut BlockStatement) {
         while !self.cur_token_is(TokenKind::RBrace) && !self.cur_token_is(TokenKind::EOF) {
             if let Some(stmt) = self.parse_statement() {
                 block.statements.push(stmt);
             }
             self.next_token();
         }
-
-        block
     }
     fn parse_function_literal(&mut self) -> ExpressionNode {
         let mut func_lit = FunctionLiteral {
             token: self.cur_token.clone(),
             parameters: Vec::new(),
             body: Default::default(),
+            variadic: false,
         };
 
         if !self.expect_peek(TokenKind::LParen) {
             return ExpressionNode::None;
         }
 
-        func_lit.parameters = self
-            .parse_function_parameters()
-            .expect("error parsing parameters");
+        match self.parse_binding_list(TokenKind::RParen) {
+            Some((parameters, variadic)) => {
+                func_lit.parameters = parameters;
+                func_lit.variadic = variadic;
+            }
+            None => return ExpressionNode::None,
+        }
 
         if !self.expect_peek(TokenKind::LBrace) {
             return ExpressionNode::None;
@@ -396,13 +798,50 @@ impl Parser {
         ExpressionNode::Function(func_lit)
     }
 
+    fn parse_macro_literal(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
+
+        if !self.expect_peek(TokenKind::LParen) {
+            return ExpressionNode::None;
+        }
+
+        let parameters = match self.parse_binding_list(TokenKind::RParen) {
+            Some((_, true)) => {
+                self.errors
+                    .push(String::from("macros cannot take a rest parameter"));
+                return ExpressionNode::None;
+            }
+            Some((parameters, false)) => parameters,
+            None => return ExpressionNode::None,
+        };
+
+        if !self.expect_peek(TokenKind::LBrace) {
+            return ExpressionNode::None;
+        }
+
+        ExpressionNode::Macro(MacroLiteral {
+            token,
+            parameters,
+            body: self.parse_block_statement(),
+        })
+    }
+
     fn parse_string_literal(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
         ExpressionNode::StringExp(StringLiteral {
-            token: self.cur_token.clone(),
-            value: self.cur_token.literal.clone(),
+            value: token.literal.to_string(),
+            raw: token.kind == TokenKind::RawString,
+            token,
         })
     }
 
+    fn parse_char_literal(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
+        // the lexer only emits `Char` for exactly one character
+        let value = token.literal.chars().next().unwrap_or_default();
+        ExpressionNode::Char(CharLiteral { token, value })
+    }
+
     fn parse_array_literal(&mut self) -> ExpressionNode {
         let array_literal = ArrayLiteral {
             token: self.cur_token.clone(),
@@ -412,16 +851,82 @@ impl Parser {
         ExpressionNode::Array(array_literal)
     }
 
-    fn parse_hash_literal(&mut self) -> ExpressionNode {
+    /// `{` opens either a hash literal or a block expression. `{}` and a first
+    /// expression followed by `:` make a hash; anything else is a block whose
+    /// value is its last statement's.
+    fn parse_brace_expression(&mut self) -> ExpressionNode {
+        let token = self.cur_token.clone();
+
+        if self.peek_token_is(&TokenKind::RBrace) {
+            return self.parse_hash_literal(token, None);
+        }
+        if matches!(
+            self.peek_token.kind,
+            TokenKind::Let
+                | TokenKind::Const
+                | TokenKind::Return
+                | TokenKind::While
+                | TokenKind::Break
+                | TokenKind::Continue
+        ) {
+            return ExpressionNode::BlockExp(self.parse_block_statement());
+        }
+
+        self.next_token();
+        // `fn name(...)` is a statement too, but only the token after `fn`
+        // tells it apart from a function literal
+        if self.cur_token_is(TokenKind::Function) && self.peek_token_is(&TokenKind::Ident) {
+            let mut block = BlockStatement {
+                token,
+                statements: Vec::new(),
+            };
+            self.parse_block_rest(&mut block);
+            return ExpressionNode::BlockExp(block);
+        }
+
+        let first_token = self.cur_token.clone();
+        let first = self.parse_expression(PrecedenceLevel::Lowest);
+
+        if self.peek_token_is(&TokenKind::Colon) {
+            return self.parse_hash_literal(token, Some(first));
+        }
+
+        let mut block = BlockStatement {
+            token,
+            statements: vec![StatementNode::Expression(ExpressionStatement {
+                token: first_token,
+                expression: first,
+            })],
+        };
+        if self.peek_token_is(&TokenKind::Semicolon) {
+            self.next_token();
+        }
+        self.next_token();
+        self.parse_block_rest(&mut block);
+
+        ExpressionNode::BlockExp(block)
+    }
+
+    /// Parses the pairs of a hash literal. `first_key` is set when
+    /// `parse_brace_expression` already consumed the first key.
+    fn parse_hash_literal(
+        &mut self,
+        token: Token,
+        mut first_key: Option<ExpressionNode>,
+    ) -> ExpressionNode {
         let mut hash = HashLiteral {
-            token: self.cur_token.clone(),
+            token,
             pairs: Default::default(),
         };
 
-        while !self.peek_token_is(&TokenKind::RBrace) {
-            self.next_token();
-
-            let key = self.parse_expression(PrecedenceLevel::Lowest);
+        while first_key.is_some() || !self.peek_token_is(&TokenKind::RBrace) {
+            let key = match first_key.take() {
+                Some(key) => key,
+                None => {
+                    self.next_token();
+                    self.parse_expression(PrecedenceLevel::Lowest)
+                }
+            };
 
             if !self.expect_peek(TokenKind::Colon) {
                 return ExpressionNode::None;
@@ -445,38 +950,48 @@ impl Parser {
         ExpressionNode::Hash(hash)
     }
 
-    fn parse_function_parameters(&mut self) -> Option<Vec<Identifier>> {
+    /// Reads comma-separated names up to `end_token`, as in a parameter list or
+    /// a destructuring pattern. The last name may be a `...rest` name, which
+    /// the returned flag reports.
+    fn parse_binding_list(&mut self, end_token: TokenKind) -> Option<(Vec<Identifier>, bool)> {
         let mut identifiers = Vec::new();
+        let mut variadic = false;
 
-        if self.peek_token_is(&TokenKind::RParen) {
+        if self.peek_token_is(&end_token) {
             self.next_token();
-            return Some(identifiers);
+            return Some((identifiers, variadic));
         }
 
-        self.next_token();
+        loop {
+            self.next_token();
 
-        let ident = Identifier {
-            token: self.cur_token.clone(),
-            value: self.cur_token.literal.clone(),
-        };
+            if self.cur_token_is(TokenKind::Ellipsis) {
+                if !self.expect_peek(TokenKind::Ident) {
+                    return None;
+                }
+                variadic = true;
+            }
 
-        identifiers.push(ident);
+            identifiers.push(self.cur_identifier());
 
-        while self.peek_token_is(&TokenKind::Comma) {
-            self.next_token();
+            if !self.peek_token_is(&TokenKind::Comma) {
+                break;
+            }
+            if variadic {
+                self.errors.push(String::from(match end_token {
+                    TokenKind::RBracket => "rest name must be the last name in the pattern",
+                    _ => "rest parameter must be the last parameter",
+                }));
+                return None;
+            }
             self.next_token();
-            let ident = Identifier {
-                token: self.cur_token.clone(),
-                value: self.cur_token.literal.clone(),
-            };
-            identifiers.push(ident);
         }
 
-        if !self.expect_peek(TokenKind::RParen) {
+        if !self.expect_peek(end_token) {
             return None;
         }
 
-        Some(identifiers)
+        Some((identifiers, variadic))
     }
 
     fn parse_call_expression(&mut self, function: ExpressionNode) -> ExpressionNode {
@@ -492,6 +1007,48 @@ impl Parser {
         ExpressionNode::Call(exp)
     }
 
+    /// Parses `value |> f` as the call `f(value)`. It is left-associative, so
+    /// `x |> f |> g` is `g(f(x))`.
+    fn parse_pipe_expression(&mut self, value: ExpressionNode) -> ExpressionNode {
+        self.next_token();
+        let token = self.cur_token.clone();
+
+        let precedence = self.cur_precedence();
+        self.next_token();
+        let function = self.parse_expression(precedence);
+
+        ExpressionNode::Call(CallExpression {
+            token,
+            function: Box::new(function),
+            arguments: vec![value],
+        })
+    }
+
+    /// Parses `receiver.method(args)` as the plain call `method(receiver, args)`,
+    /// so any function can be called method-style.
+    fn parse_method_call_expression(&mut self, receiver: ExpressionNode) -> ExpressionNode {
+        self.next_token(); // consume the .
+
+        if !self.expect_peek(TokenKind::Ident) {
+            return ExpressionNode::None;
+        }
+        let method = self.parse_identifier();
+
+        if !self.expect_peek(TokenKind::LParen) {
+            return ExpressionNode::None;
+        }
+        let mut exp = CallExpression {
+            token: self.cur_token.clone(),
+            function: Box::new(method),
+            arguments: vec![receiver],
+        };
+
+        exp.arguments
+            .extend(self.parse_expression_list(TokenKind::RParen));
+
+        ExpressionNode::Call(exp)
+    }
+
     fn parse_index_expression(&mut self, left: ExpressionNode) -> ExpressionNode {
         self.next_token(); //consume the [
 
@@ -540,16 +1097,30 @@ impl Parser {
 
 #[cfg(test)]
 mod tests {
-    use std::any;
+    use std::{any, panic, rc::Rc};
 
-    use super::Parser;
+    use super::{Parser, PrecedenceLevel};
     use crate::ast::{ExpressionNode, Identifier, Node, StatementNode};
     use crate::lexer::Lexer;
     use crate::token::TokenKind;
 
     #[test]
-    fn test_let_statements() {
-        let tests: Vec<(&str, &str, Box<dyn any::Any>)> = vec![
+    fn test_empty_input() {
+        for input in ["", "   ", "\n\n", " \t\n // only a comment\n"] {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+            assert!(program.statements.is_empty(), "input: {:?}", input);
+            assert_eq!(program.token_literal(), "", "input: {:?}", input);
+            assert_eq!(program.to_string(), "", "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_let_statements() {
+        let tests: Vec<(&str, &str, Box<dyn any::Any>)> = vec![
             ("let x = 5;", "x", Box::new(5)),
             ("let y = 10;", "y", Box::new(10)),
             ("let foobar = 838383;", "foobar", Box::new(838383)),
@@ -650,7 +1221,7 @@ mod tests {
             StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
                 ExpressionNode::IdentifierNode(ident) => {
                     assert_eq!(
-                        ident.value, "foobar",
+                        &*ident.value, "foobar",
                         "ident.value not 'foobar'. got={}",
                         ident.value
                     );
@@ -719,11 +1290,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_float_literal_expression() {
+        let tests = vec![("2.5", "2.5"), ("10.0", "10.0"), ("-0.5", "(-0.5)")];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+    }
+
     #[test]
     fn test_parsing_prefix_expressions() {
         let prefix_tests: Vec<(&str, &str, Box<dyn any::Any>)> = vec![
             ("!5", "!", Box::new(5)),
             ("-15", "-", Box::new(15)),
+            ("+5", "+", Box::new(5)),
             ("!true", "!", Box::new(true)),
             ("!false", "!", Box::new(false)),
         ];
@@ -775,7 +1361,9 @@ mod tests {
 
     #[test]
     fn test_parsing_infix_expressions() {
-        let infix_tests: Vec<(&str, Box<dyn any::Any>, &str, Box<dyn any::Any>)> = vec![
+        type InfixTest<'a> = (&'a str, Box<dyn any::Any>, &'a str, Box<dyn any::Any>);
+
+        let infix_tests: Vec<InfixTest> = vec![
             ("5 + 5;", Box::new(5), "+", Box::new(5)),
             ("5 - 5;", Box::new(5), "-", Box::new(5)),
             ("5 * 5;", Box::new(5), "*", Box::new(5)),
@@ -828,6 +1416,8 @@ mod tests {
         let tests = vec![
             ("-a * b", "((-a) * b)"),
             ("!-a", "(!(-a))"),
+            ("+5", "(+5)"),
+            ("a + +b", "(a + (+b))"),
             ("a + b + c", "((a + b) + c)"),
             ("a + b - c", "((a + b) - c)"),
             ("a * b * c", "((a * b) * c)"),
@@ -867,6 +1457,12 @@ mod tests {
                 "add(a * b[2], b[1], 2 * [1, 2][1])",
                 "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
             ),
+            ("5 == 5", "(5 == 5)"),
+            ("5 != 5", "(5 != 5)"),
+            ("a == b != c", "((a == b) != c)"),
+            ("a + b == c * d", "((a + b) == (c * d))"),
+            ("-a != -b", "((-a) != (-b))"),
+            ("!(a == b)", "(!(a == b))"),
         ];
 
         for test in tests {
@@ -882,6 +1478,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_set_precedence() {
+        let mut parser = Parser::new(Lexer::new("2 + 3 * 4"));
+        parser.set_precedence(TokenKind::Asterisk, PrecedenceLevel::Sum);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+        assert_eq!(program.to_string(), "((2 + 3) * 4)");
+
+        let mut parser = Parser::new(Lexer::new("2 + 3 * 4"));
+        assert_eq!(parser.parse_program().to_string(), "(2 + (3 * 4))");
+    }
+
+    #[test]
+    fn test_equality_operators_round_trip() {
+        let tests = vec![
+            ("5 == 5", TokenKind::EQ, "=="),
+            ("5 != 5", TokenKind::NotEQ, "!="),
+        ];
+
+        for test in tests {
+            let lexer = Lexer::new(test.0);
+            let mut parser = Parser::new(lexer);
+
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+
+            match &program.statements[0] {
+                StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
+                    ExpressionNode::Infix(infix_exp) => {
+                        assert_eq!(
+                            infix_exp.token.kind, test.1,
+                            "infix_exp.token.kind not {}. got={}",
+                            test.1, infix_exp.token.kind
+                        );
+                        assert_eq!(
+                            infix_exp.operator, test.2,
+                            "infix_exp.operator not '{}'. got={}",
+                            test.2, infix_exp.operator
+                        );
+                        assert_eq!(program.to_string(), format!("(5 {} 5)", test.2));
+                    }
+                    other => panic!("exp not Infix. got={:?}", other),
+                },
+                other => panic!("stmt not ExpressionStatement. got={:?}", other),
+            }
+        }
+    }
+
     #[test]
     fn test_boolean_expression() {
         let input = r#"
@@ -1096,35 +1742,29 @@ mod tests {
                         function.parameters.len()
                     );
 
-                    match &function.parameters[0] {
-                        Identifier { token, value } => {
-                            assert_eq!(
-                                value, "x",
-                                "function literal parameter is not 'x'. got={}",
-                                value
-                            );
-                            assert_eq!(
-                                token.literal, "x",
-                                "function literal parameter is not 'x'. got={}",
-                                token.literal
-                            )
-                        }
-                    }
+                    let Identifier { token, value } = &function.parameters[0];
+                    assert_eq!(
+                        &**value, "x",
+                        "function literal parameter is not 'x'. got={}",
+                        value
+                    );
+                    assert_eq!(
+                        &*token.literal, "x",
+                        "function literal parameter is not 'x'. got={}",
+                        token.literal
+                    );
 
-                    match &function.parameters[1] {
-                        Identifier { token, value } => {
-                            assert_eq!(
-                                value, "y",
-                                "function literal parameter is not 'y'. got={}",
-                                value
-                            );
-                            assert_eq!(
-                                token.literal, "y",
-                                "function literal parameter is not 'y'. got={}",
-                                token.literal
-                            )
-                        }
-                    }
+                    let Identifier { token, value } = &function.parameters[1];
+                    assert_eq!(
+                        &**value, "y",
+                        "function literal parameter is not 'y'. got={}",
+                        value
+                    );
+                    assert_eq!(
+                        &*token.literal, "y",
+                        "function literal parameter is not 'y'. got={}",
+                        token.literal
+                    );
 
                     assert_eq!(
                         function.body.statements.len(),
@@ -1189,20 +1829,17 @@ mod tests {
                         );
 
                         for (i, param) in test.1.into_iter().enumerate() {
-                            match &function.parameters[i] {
-                                Identifier { token, value } => {
-                                    assert_eq!(
-                                        value, param,
-                                        "function literal parameter is not '{}'. got={}",
-                                        param, value
-                                    );
-                                    assert_eq!(
-                                        token.literal, param,
-                                        "function literal parameter is not '{}'. got={}",
-                                        param, token.literal
-                                    )
-                                }
-                            }
+                            let Identifier { token, value } = &function.parameters[i];
+                            assert_eq!(
+                                &**value, param,
+                                "function literal parameter is not '{}'. got={}",
+                                param, value
+                            );
+                            assert_eq!(
+                                &*token.literal, param,
+                                "function literal parameter is not '{}'. got={}",
+                                param, token.literal
+                            );
                         }
                     }
                     other => {
@@ -1217,6 +1854,445 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_variadic_function_parameters() {
+        let input = "fn(first, ...rest) { rest }";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+
+        match &program.statements[0] {
+            StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
+                ExpressionNode::Function(function) => {
+                    let params: Vec<&str> = function.parameters.iter().map(|p| &*p.value).collect();
+                    assert_eq!(params, vec!["first", "rest"]);
+                    assert!(function.variadic, "function is not variadic");
+                    assert_eq!(function.to_string(), "fn(first, ...rest) rest");
+                }
+                other => panic!("exp not FunctionLiteral. got={:?}", other),
+            },
+            other => panic!("stmt not ExpressionStatement. got={:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_macro_literal_parsing() {
+        let lexer = Lexer::new("macro(x, y) { x + y; }");
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+        assert_eq!(program.to_string(), "macro(x, y) (x + y)");
+
+        let mut parser = Parser::new(Lexer::new("macro(...args) { args }"));
+        parser.parse_program();
+        assert_eq!(
+            parser.errors().first().map(String::as_str),
+            Some("macros cannot take a rest parameter")
+        );
+    }
+
+    #[test]
+    fn test_integer_literal_leading_zeros() {
+        let tests = vec![
+            ("0", Ok(0)),
+            ("1234", Ok(1234)),
+            (
+                "00",
+                Err("integer literal '00' must not have leading zeros"),
+            ),
+            (
+                "0123",
+                Err("integer literal '0123' must not have leading zeros"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            match expected {
+                Ok(value) => {
+                    check_parser_errors(&parser);
+                    match &program.statements[0] {
+                        StatementNode::Expression(exp_stmt) => {
+                            test_integer_literal(&exp_stmt.expression, value)
+                        }
+                        other => panic!("statement is not ExpressionStatement. got={:?}", other),
+                    }
+                }
+                Err(message) => assert_eq!(
+                    parser.errors().first().map(String::as_str),
+                    Some(message),
+                    "input: {}",
+                    input
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_let_type_annotations() {
+        let tests = vec![
+            ("let x: int = 5;", "x", Some("int"), "let x: int = 5;"),
+            (
+                "let flag: bool = true;",
+                "flag",
+                Some("bool"),
+                "let flag: bool = true;",
+            ),
+            ("let y = 5;", "y", None, "let y = 5;"),
+        ];
+
+        for (input, name, annotation, printed) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+            test_let_statement(&program.statements[0], name);
+
+            match &program.statements[0] {
+                StatementNode::Let(let_stmt) => assert_eq!(
+                    let_stmt.type_annotation.as_ref().map(|t| &*t.value),
+                    annotation,
+                    "input: {}",
+                    input
+                ),
+                other => panic!("statement is not LetStatement. got={:?}", other),
+            }
+            assert_eq!(program.to_string(), printed);
+        }
+    }
+
+    #[test]
+    fn test_unknown_type_annotation() {
+        let lexer = Lexer::new("let x: float = 5;");
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        assert_eq!(
+            parser.errors(),
+            &["unknown type annotation 'float' for 'x', expected one of: int, bool, string"]
+        );
+        // the statement is still produced so evaluation can ignore the annotation
+        test_let_statement(&program.statements[0], "x");
+    }
+
+    #[test]
+    fn test_rest_parameter_must_be_last() {
+        let lexer = Lexer::new("fn(...rest, last) { rest }");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(
+            parser.errors().first().map(String::as_str),
+            Some("rest parameter must be the last parameter")
+        );
+    }
+
+    #[test]
+    fn test_named_function_statement() {
+        let input = "fn add(x, y) { x + y } add(1, 2);";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            2,
+            "program.statements does not contain 2 statements. got={}",
+            program.statements.len()
+        );
+
+        test_let_statement(&program.statements[0], "add");
+        assert_eq!(
+            program.statements[0].to_string(),
+            "let add = fn(x, y) (x + y);"
+        );
+    }
+
+    #[test]
+    fn test_const_statement() {
+        let lexer = Lexer::new("const PI = 3; let x = PI;");
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+        assert_eq!(program.to_string(), "const PI = 3;let x = PI;");
+
+        match &program.statements[..] {
+            [StatementNode::Let(constant), StatementNode::Let(variable)] => {
+                assert_eq!(&*constant.name.value, "PI");
+                assert!(constant.is_constant());
+                test_literal_expression(&constant.value, Box::new(3));
+                assert!(!variable.is_constant());
+            }
+            other => panic!("expected two let statements. got={:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_let_without_value() {
+        let lexer = Lexer::new("let x; let y: int; x = 1;");
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+        assert_eq!(program.to_string(), "let x;let y: int;(x = 1)");
+
+        match &program.statements[..] {
+            [StatementNode::Let(x), StatementNode::Let(y), _] => {
+                assert_eq!(&*x.name.value, "x");
+                assert!(matches!(x.value, ExpressionNode::None), "got {:?}", x.value);
+                assert!(matches!(y.value, ExpressionNode::None), "got {:?}", y.value);
+            }
+            other => panic!("expected two let statements. got={:?}", other),
+        }
+
+        // a constant could never be given its value later
+        let mut parser = Parser::new(Lexer::new("const PI;"));
+        parser.parse_program();
+        assert_eq!(
+            parser.errors().first().map(String::as_str),
+            Some("expected next token to be Assign, got Semicolon instead")
+        );
+    }
+
+    #[test]
+    fn test_destructuring_let() {
+        let tests = vec![
+            (
+                "let [a, b] = [1, 2];",
+                vec!["a", "b"],
+                None,
+                "let [a, b] = [1, 2];",
+            ),
+            (
+                "const [head, ...tail] = xs",
+                vec!["head"],
+                Some("tail"),
+                "const [head, ...tail] = xs;",
+            ),
+            ("let [] = [];", vec![], None, "let [] = [];"),
+        ];
+
+        for (input, names, rest, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            match &program.statements[..] {
+                [StatementNode::Destructure(destructure)] => {
+                    let got: Vec<_> = destructure.names.iter().map(|n| &*n.value).collect();
+                    assert_eq!(got, names, "input: {}", input);
+                    assert_eq!(
+                        destructure.rest.as_ref().map(|r| &*r.value),
+                        rest,
+                        "input: {}",
+                        input
+                    );
+                }
+                other => panic!("expected one destructuring let. got={:?}", other),
+            }
+            assert_eq!(program.to_string(), expected);
+        }
+
+        let mut parser = Parser::new(Lexer::new("let [...rest, last] = xs;"));
+        parser.parse_program();
+        assert_eq!(
+            parser.errors().first().map(String::as_str),
+            Some("rest name must be the last name in the pattern")
+        );
+    }
+
+    #[test]
+    fn test_assign_expressions() {
+        let tests = vec![
+            ("x = 5", "(x = 5)"),
+            ("x = y + 1", "(x = (y + 1))"),
+            ("x = y = 1", "(x = (y = 1))"),
+            ("x = y == 1", "(x = (y == 1))"),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_invalid_assignment_target() {
+        let lexer = Lexer::new("a + b = 2");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(parser.errors(), &["invalid assignment target: (a + b)"]);
+    }
+
+    #[test]
+    fn test_match_expression() {
+        let input = r#"match (x) { 1 => "one", y + 1 => { y }, _ => "other", }"#;
+
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        let match_exp = match &program.statements[..] {
+            [StatementNode::Expression(exp_stmt)] => match &exp_stmt.expression {
+                ExpressionNode::Match(match_exp) => match_exp,
+                other => panic!("exp not MatchExpression. got={:?}", other),
+            },
+            other => panic!("expected one expression statement. got={:?}", other),
+        };
+
+        test_identifier(&match_exp.subject, "x".to_string());
+        assert_eq!(match_exp.arms.len(), 3);
+        match &match_exp.arms[0].pattern {
+            Some(pattern) => test_integer_literal(pattern, 1),
+            None => panic!("first arm should not be a wildcard"),
+        }
+        match &match_exp.arms[1].pattern {
+            Some(pattern) => {
+                test_infix_expression(pattern, Box::new("y"), "+".to_string(), Box::new(1_i64))
+            }
+            None => panic!("second arm should not be a wildcard"),
+        }
+        assert!(matches!(
+            match_exp.arms[1].value,
+            ExpressionNode::BlockExp(_)
+        ));
+        assert!(match_exp.arms[2].pattern.is_none());
+        assert_eq!(
+            program.to_string(),
+            "match (x) { 1 => one, (y + 1) => { y }, _ => other }"
+        );
+
+        let tests = vec![
+            (
+                "match (x) { 1 2 }",
+                "expected next token to be FatArrow, got Int instead",
+            ),
+            (
+                "match (x) { 1 => 2 3 => 4 }",
+                "expected next token to be Comma, got Int instead",
+            ),
+        ];
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse_program();
+            assert_eq!(
+                parser.errors().first().map(String::as_str),
+                Some(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_while_statement() {
+        let input = "while (x < 10) { let x = x + 1; }";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+
+        assert_eq!(
+            program.statements.len(),
+            1,
+            "program.statements does not contain 1 statements. got={}",
+            program.statements.len()
+        );
+
+        match &program.statements[0] {
+            StatementNode::While(while_stmt) => {
+                test_infix_expression(
+                    &while_stmt.condition,
+                    Box::new("x"),
+                    String::from("<"),
+                    Box::new(10_i64),
+                );
+                assert_eq!(
+                    while_stmt.body.statements.len(),
+                    1,
+                    "body is not 1 statement. got={}",
+                    while_stmt.body.statements.len()
+                );
+                test_let_statement(&while_stmt.body.statements[0], "x");
+            }
+            other => panic!("statement is not While. got={:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_break_and_continue_statements() {
+        let input = "while (x) { break; continue }";
+        let mut parser = Parser::new(Lexer::new(input));
+        let program = parser.parse_program();
+        check_parser_errors(&parser);
+
+        match &program.statements[0] {
+            StatementNode::While(while_stmt) => {
+                let body = &while_stmt.body.statements;
+                assert!(
+                    matches!(body[0], StatementNode::Break(_)),
+                    "got {:?}",
+                    body[0]
+                );
+                assert!(
+                    matches!(body[1], StatementNode::Continue(_)),
+                    "got {:?}",
+                    body[1]
+                );
+            }
+            other => panic!("statement is not While. got={:?}", other),
+        }
+        assert_eq!(program.to_string(), "whilex break;continue;");
+    }
+
+    #[test]
+    fn test_identifiers_are_interned() {
+        let input = "let total = 1; let other = total + total;";
+
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+
+        let (name, value) = match (&program.statements[0], &program.statements[1]) {
+            (StatementNode::Let(first), StatementNode::Let(second)) => (&first.name, &second.value),
+            other => panic!("statements are not LetStatements. got={:?}", other),
+        };
+        match value {
+            ExpressionNode::Infix(infix) => match (infix.left.as_ref(), infix.right.as_ref()) {
+                (ExpressionNode::IdentifierNode(left), ExpressionNode::IdentifierNode(right)) => {
+                    assert!(Rc::ptr_eq(&name.value, &left.value));
+                    assert!(Rc::ptr_eq(&left.value, &right.value));
+                }
+                other => panic!("operands are not identifiers. got={:?}", other),
+            },
+            other => panic!("value is not an InfixExpression. got={:?}", other),
+        }
+    }
+
     #[test]
     fn test_call_expression_parsing() {
         let input = "add(1, 2 * 3, 4 + 5);";
@@ -1272,6 +2348,136 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_method_call_parsing() {
+        let tests = vec![
+            (r#""hello".len()"#, "len(hello)"),
+            ("[1, 2, 3].first()", "first([1, 2, 3])"),
+            ("xs.push(4)", "push(xs, 4)"),
+            ("xs.map(f).filter(g)", "filter(map(xs, f), g)"),
+            ("a + b.len() * 2", "(a + (len(b) * 2))"),
+            ("xs[0].len()", "len((xs[0]))"),
+            ("-xs.len()", "(-len(xs))"),
+            ("(a + b).abs()", "abs((a + b))"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+
+        let program = Parser::new(Lexer::new("xs.push(1, 2)")).parse_program();
+        match &program.statements[0] {
+            StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
+                ExpressionNode::Call(call_exp) => {
+                    test_identifier(&call_exp.function, "push".to_string());
+                    assert_eq!(call_exp.arguments.len(), 3);
+                    test_identifier(&call_exp.arguments[0], "xs".to_string());
+                    test_literal_expression(&call_exp.arguments[1], Box::new(1));
+                    test_literal_expression(&call_exp.arguments[2], Box::new(2));
+                }
+                other => panic!("exp not CallExpression. got={:?}", other),
+            },
+            other => panic!("stmt not ExpressionStatement. got={:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_arrow_function_parsing() {
+        let tests = vec![
+            ("x -> x + 1", "fn(x) return (x + 1);"),
+            ("(x, y) -> x + y", "fn(x, y) return (x + y);"),
+            ("(x) -> x", "fn(x) return x;"),
+            ("() -> 1", "fn() return 1;"),
+            ("map(xs, x -> x * 2)", "map(xs, fn(x) return (x * 2);)"),
+            ("f((a, b) -> a, 1)", "f(fn(a, b) return a;, 1)"),
+            ("x -> y -> x + y", "fn(x) return fn(y) return (x + y);;"),
+            ("(x + 1) * 2", "((x + 1) * 2)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_arrow_function_errors() {
+        let tests = vec![
+            (
+                "(x, 1) -> x",
+                "arrow function parameters must be names, got 1",
+            ),
+            (
+                "(x, y) + 1",
+                "expected next token to be Arrow, got Plus instead",
+            ),
+            ("()", "expected next token to be Arrow, got EOF instead"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse_program();
+
+            assert_eq!(
+                parser.errors().first().map(String::as_str),
+                Some(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_pipe_parsing() {
+        let tests = vec![
+            ("5 |> double", "double(5)"),
+            ("5 |> double |> inc", "inc(double(5))"),
+            ("a + 1 |> f", "f((a + 1))"),
+            ("x == y |> f", "f((x == y))"),
+            ("x = 2 |> f", "(x = f(2))"),
+            ("xs |> make(1)", "make(1)(xs)"),
+            ("3 |> x -> x * 2", "fn(x) return (x * 2);(3)"),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            let program = parser.parse_program();
+            check_parser_errors(&parser);
+
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_method_call_errors() {
+        let tests = vec![
+            ("xs.1()", "expected next token to be Ident, got Int instead"),
+            (
+                "xs.len",
+                "expected next token to be LParen, got EOF instead",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let mut parser = Parser::new(Lexer::new(input));
+            parser.parse_program();
+
+            assert_eq!(
+                parser.errors().first().map(String::as_str),
+                Some(expected),
+                "input: {}",
+                input
+            );
+        }
+    }
+
     #[test]
     fn test_string_literal_expression() {
         let input = r#""Hello, World!""#;
@@ -1407,17 +2613,15 @@ mod tests {
                         hash_literal.pairs.len()
                     );
 
-                    let expected = vec![
+                    let expected = [
                         ("one".to_string(), 1),
                         ("two".to_string(), 2),
                         ("three".to_string(), 3),
                     ];
-                    let mut curr_idx: usize = 0;
 
-                    for (_, value) in &hash_literal.pairs {
+                    for (curr_idx, (_, value)) in hash_literal.pairs.iter().enumerate() {
                         let expected_value = expected[curr_idx].1;
                         test_integer_literal(value, expected_value);
-                        curr_idx += 1;
                     }
                 }
                 other => panic!("exp not HashLiteral. got={:?}", other),
@@ -1452,6 +2656,85 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_semicolon_free_statements() {
+        let tests = vec![
+            ("5\n10\n15", vec!["5", "10", "15"]),
+            (
+                "let x = 1\nlet y = x\ny",
+                vec!["let x = 1;", "let y = x;", "y"],
+            ),
+            (
+                "if (x) { 1\n2 } else { 3 }\nfn(a) { a\nreturn a }",
+                vec!["ifx 12else 3", "fn(a) areturn a;"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+
+            let statements: Vec<String> = program
+                .statements
+                .iter()
+                .map(|stmt| stmt.to_string())
+                .collect();
+            assert_eq!(statements, expected, "input: {:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_parsing_block_expressions() {
+        let tests = vec![
+            (
+                "let y = { let x = 5; x + 1 };",
+                "let y = { let x = 5;(x + 1) };",
+            ),
+            ("{ x; y }", "{ xy }"),
+            ("{ x } + 1", "({ x } + 1)"),
+            ("{ return 1; }", "{ return 1; }"),
+            (r#"{"a": 1}"#, "{a: 1}"),
+            ("{x: y, 1: 2}", "{x: y, 1: 2}"),
+            ("{}", "{}"),
+            ("{ fn f() { 1 } f() }", "{ let f = fn() 1;f() }"),
+            ("{ fn(x) { x } }", "{ fn(x) x }"),
+        ];
+
+        for (input, expected) in tests {
+            let lexer = Lexer::new(input);
+            let mut parser = Parser::new(lexer);
+            let program = parser.parse_program();
+
+            check_parser_errors(&parser);
+            assert_eq!(program.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_block_statements() {
+        let lexer = Lexer::new("{ let x = 1; x } {}");
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+
+        check_parser_errors(&parser);
+        assert_eq!(program.statements.len(), 2);
+        match &program.statements[0] {
+            StatementNode::Block(block) => {
+                assert_eq!(block.statements.len(), 2);
+                assert_eq!(block.to_string(), "let x = 1;x");
+            }
+            other => panic!("statement is not a Block. got={:?}", other),
+        }
+        // `{}` is still an empty hash
+        assert!(matches!(
+            &program.statements[1],
+            StatementNode::Expression(_)
+        ));
+    }
+
     #[test]
     fn test_parsing_hash_literals_with_expressions() {
         let input = r#"{ "one": 0 + 1, "two": 10 - 8, "three": 15 / 5 }"#;
@@ -1472,14 +2755,13 @@ mod tests {
                         hash_literal.pairs.len()
                     );
 
-                    let expected = vec![
+                    let expected = [
                         ("one".to_string(), (0, "+", 1)),
                         ("two".to_string(), (10, "-", 8)),
                         ("three".to_string(), (15, "/", 5)),
                     ];
-                    let mut curr_idx: usize = 0;
 
-                    for (_, value) in &hash_literal.pairs {
+                    for (curr_idx, (_, value)) in hash_literal.pairs.iter().enumerate() {
                         let expected_value = &expected[curr_idx];
                         test_func_for_key(
                             value,
@@ -1487,7 +2769,6 @@ mod tests {
                             expected_value.1 .1,
                             expected_value.1 .2,
                         );
-                        curr_idx += 1;
                     }
                 }
                 other => panic!("exp not HashLiteral. got={:?}", other),
@@ -1496,6 +2777,62 @@ mod tests {
         }
     }
 
+    /// A tiny xorshift generator, so the fuzz test is reproducible without
+    /// pulling in a dependency.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: usize) -> usize {
+            (self.next() % bound as u64) as usize
+        }
+    }
+
+    #[test]
+    fn test_random_token_soup_never_panics() {
+        const ALPHABET: &[&str] = &[
+            "let", "const", "fn", "macro", "if", "else", "return", "while", "break", "continue",
+            "true", "false", "x", "y", "0", "1", "0123", "\"s\"", "`r`", "\"{x}\"", "=", "+", "-",
+            "!", "*", "/", "<", ">", "==", "!=", ",", ";", ":", ".", "...", "(", ")", "{", "}",
+            "[", "]", "@", "quote", "unquote", "\n", "match", "=>", "_", "'c'", "'", "->", "|>",
+            "|",
+        ];
+
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+        for _ in 0..5_000 {
+            let len = 1 + rng.below(24);
+            let snippet = (0..len)
+                .map(|_| ALPHABET[rng.below(ALPHABET.len())])
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            let parsed = panic::catch_unwind(|| {
+                let mut parser = Parser::new(Lexer::new(&snippet));
+                // rendering must not panic either, whatever was recovered
+                parser.parse_program().to_string()
+            });
+            assert!(parsed.is_ok(), "parsing panicked on: {:?}", snippet);
+        }
+    }
+
+    #[test]
+    fn test_illegal_character_error() {
+        let lexer = Lexer::new("let x = 1;\nlet y = @;");
+        let mut parser = Parser::new(lexer);
+        parser.parse_program();
+
+        assert_eq!(
+            parser.errors().first().map(String::as_str),
+            Some("unexpected character '@' at line 2, col 9")
+        );
+    }
+
     fn test_func_for_key(exp: &ExpressionNode, left: i64, operator: &str, right: i64) {
         match exp {
             ExpressionNode::Infix(infix_exp) => {
@@ -1515,7 +2852,7 @@ mod tests {
 
     pub fn check_parser_errors(parser: &Parser) {
         let errors = parser.errors();
-        if errors.len() == 0 {
+        if errors.is_empty() {
             return;
         }
 
@@ -1553,7 +2890,7 @@ mod tests {
         match exp {
             ExpressionNode::IdentifierNode(identifier_exp) => {
                 assert_eq!(
-                    identifier_exp.value, value,
+                    &*identifier_exp.value, value,
                     "identifier_exp.value not '{}'. got={}",
                     value, identifier_exp.value
                 );
@@ -1579,10 +2916,11 @@ mod tests {
                 Some(int_exp) => {
                     test_integer_literal(exp, *int_exp);
                 }
-                None => match expected.downcast_ref::<bool>() {
-                    Some(bool) => test_boolean_literal(exp, bool.to_owned()),
-                    None => (),
-                },
+                None => {
+                    if let Some(bool) = expected.downcast_ref::<bool>() {
+                        test_boolean_literal(exp, bool.to_owned())
+                    }
+                }
             },
         }
     }
@@ -1642,7 +2980,7 @@ mod tests {
         match stmt {
             StatementNode::Let(let_stmt) => {
                 assert_eq!(
-                    let_stmt.name.value, expected,
+                    &*let_stmt.name.value, expected,
                     "LetStatement name value not {}. got {}",
                     expected, let_stmt.name.value
                 );
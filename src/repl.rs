@@ -1,55 +1,271 @@
+use crate::builtins::Builtins;
+use crate::evaluator::Evaluator;
 use crate::lexer::Lexer;
+use crate::object::Object;
 use crate::parser::Parser;
-use crate::{evaluator::Evaluator, object::Object};
-use std::io::{Stdin, Stdout, Write};
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::time::Instant;
 
-pub fn start(stdin: Stdin, mut stdout: Stdout) -> std::io::Result<()> {
-    let mut evaluator = Evaluator::new();
+/// Every REPL command with a one-line description, in the order `:help`
+/// lists them.
+pub const COMMANDS: &[(&str, &str)] = &[
+    (":help", "Lists these commands and the builtin functions."),
+    (
+        ":history",
+        "Lists every input entered so far, numbered from 1.",
+    ),
+    (":last", "Re-runs the most recent input."),
+    (
+        ":env",
+        "Lists the current bindings with their values and types.",
+    ),
+    (":json", "Toggles echoing each result as JSON."),
+    (":time", "Toggles printing how long each input took."),
+    (
+        ":load <path>",
+        "Evaluates a source file in the current session.",
+    ),
+    (":quit", "Leaves the REPL."),
+];
 
-    loop {
-        write!(stdout, ">> ")?;
-        stdout.flush()?;
+/// Settings for a REPL session, so embedders can brand their shell.
+#[derive(Debug, Clone)]
+pub struct ReplConfig {
+    /// Printed before each new input.
+    pub prompt: String,
+    /// Printed before the following lines of an input that spans several
+    /// lines. Reserved until multi-line input is supported.
+    pub continuation_prompt: String,
+    /// Prints parser and runtime errors in red using ANSI escape codes.
+    pub color: bool,
+}
+
+impl Default for ReplConfig {
+    fn default() -> Self {
+        ReplConfig {
+            prompt: String::from(">> "),
+            continuation_prompt: String::from("... "),
+            color: false,
+        }
+    }
+}
 
-        let mut input = String::new();
+pub fn start() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    let config = ReplConfig {
+        color: stdout.is_terminal(),
+        ..ReplConfig::default()
+    };
+    run(stdin.lock(), &mut stdout, &config)
+}
 
-        let bytes_read = stdin.read_line(&mut input);
+/// Runs the read-eval-print loop until `reader` is exhausted. Kept generic so
+/// tests can drive a session from memory and inspect everything written.
+pub fn run<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    config: &ReplConfig,
+) -> io::Result<()> {
+    let mut evaluator = Evaluator::new();
+    let mut history: Vec<String> = Vec::new();
+    let mut toggles = Toggles::default();
 
-        match bytes_read {
-            Ok(0) => {
-                writeln!(stdout, "Exiting REPL...")?;
+    loop {
+        let input = match read_input(&mut reader, writer, config) {
+            Ok(Some(input)) => input,
+            Ok(None) => {
+                writeln!(writer, "Exiting REPL...")?;
                 return Ok(());
             }
-            Ok(_) => {
-                // Successfully read input, continue with processing
-            }
             Err(e) => {
-                writeln!(stdout, "Failed to read from stdin: {}", e)?;
+                writeln!(writer, "Failed to read from stdin: {}", e)?;
                 return Err(e);
             }
-        }
+        };
 
-        let lexer: Lexer = Lexer::new(&input);
-        let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
+        let source = match input.trim() {
+            ":help" => {
+                print_help(writer)?;
+                continue;
+            }
+            ":quit" => {
+                writeln!(writer, "Exiting REPL...")?;
+                return Ok(());
+            }
+            ":history" => {
+                for (idx, line) in history.iter().enumerate() {
+                    writeln!(writer, "{}: {}", idx + 1, line)?;
+                }
+                continue;
+            }
+            ":json" => {
+                toggles.json_output = !toggles.json_output;
+                writeln!(writer, "JSON output {}", on_off(toggles.json_output))?;
+                continue;
+            }
+            ":time" => {
+                toggles.timing = !toggles.timing;
+                writeln!(writer, "Timing {}", on_off(toggles.timing))?;
+                continue;
+            }
+            ":env" => {
+                for (name, value) in evaluator.env().borrow().bindings() {
+                    writeln!(writer, "{} = {} ({})", name, value, value.object_type())?;
+                }
+                continue;
+            }
+            // re-runs the previous input without recording it again
+            ":last" => match history.last() {
+                Some(last) => last.clone(),
+                None => {
+                    writeln!(writer, "No history yet")?;
+                    continue;
+                }
+            },
+            line if line.starts_with(":load ") => {
+                let path = line[":load ".len()..].trim();
+                match fs::read_to_string(path) {
+                    Ok(contents) => {
+                        eval_input(&mut evaluator, &contents, &toggles, config, writer)?
+                    }
+                    Err(err) => writeln!(writer, "Could not load {}: {}", path, err)?,
+                }
+                continue;
+            }
+            // a blank line just prompts again
+            "" => continue,
+            line => {
+                history.push(line.to_string());
+                input
+            }
+        };
 
-        if !parser.errors().is_empty() {
-            print_parse_errors(&stdout, parser.errors())?;
-            continue;
-        }
+        eval_input(&mut evaluator, &source, &toggles, config, writer)?;
+    }
+}
 
-        let evaluated = evaluator.eval_program(program);
+fn print_help<W: Write>(writer: &mut W) -> io::Result<()> {
+    let width = COMMANDS
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    writeln!(writer, "Commands:")?;
+    for (name, description) in COMMANDS {
+        writeln!(writer, "  {:width$}  {}", name, description, width = width)?;
+    }
 
-        match &evaluated {
-            Object::StringObj(s) => writeln!(stdout, "'{}'", s)?,
-            _ => writeln!(stdout, "{}", evaluated)?,
-        }
+    let names: Vec<String> = Builtins
+        .all_builtins()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    writeln!(writer, "Builtins: {}", names.join(", "))
+}
+
+/// Prompts for and reads one line, without its line ending (`\n` or
+/// `\r\n`). Returns `None` once `reader` is exhausted.
+fn read_input<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    config: &ReplConfig,
+) -> io::Result<Option<String>> {
+    write!(writer, "{}", config.prompt)?;
+    writer.flush()?;
+
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let line = line.strip_suffix('\n').unwrap_or(&line);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    Ok(Some(line.to_string()))
+}
+
+/// Session settings flipped by REPL commands.
+#[derive(Default)]
+struct Toggles {
+    /// `:json` switches result echoing to `Object::to_json`.
+    json_output: bool,
+    /// `:time` reports how long parsing and evaluation took after each result.
+    timing: bool,
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
     }
 }
 
-fn print_parse_errors(mut stdout: &Stdout, errors: &[String]) -> std::io::Result<()> {
-    writeln!(stdout, "Oops! We ran into parser errors")?;
+fn eval_input<W: Write>(
+    evaluator: &mut Evaluator,
+    input: &str,
+    toggles: &Toggles,
+    config: &ReplConfig,
+    writer: &mut W,
+) -> io::Result<()> {
+    // lexing is lazy, so the parse time includes it
+    let started = Instant::now();
+    let lexer: Lexer = Lexer::new(input);
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse_program();
+    let parsed_in = started.elapsed();
+
+    if !parser.errors().is_empty() {
+        return print_parse_errors(writer, parser.errors(), config.color);
+    }
+
+    let started = Instant::now();
+    // an error value caught with `try` is a result, not a failure
+    let (evaluated, failed) = match evaluator.eval(program) {
+        Ok(value) => (value, false),
+        Err(err) => (Object::Error(err.to_string()), true),
+    };
+    let evaluated_in = started.elapsed();
+
+    if toggles.json_output {
+        writeln!(writer, "{}", evaluated.to_json())?;
+    } else if failed {
+        writeln!(
+            writer,
+            "{}",
+            paint_error(&evaluated.inspect(), config.color)
+        )?;
+    } else {
+        writeln!(writer, "{}", evaluated.inspect())?;
+    }
+
+    if toggles.timing {
+        writeln!(
+            writer,
+            "parsed in {:?}, evaluated in {:?}",
+            parsed_in, evaluated_in
+        )?;
+    }
+    Ok(())
+}
+
+fn print_parse_errors<W: Write>(writer: &mut W, errors: &[String], color: bool) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{}",
+        paint_error("Oops! We ran into parser errors", color)
+    )?;
     for error in errors {
-        writeln!(stdout, "{}", error)?;
+        writeln!(writer, "{}", paint_error(error, color))?;
     }
     Ok(())
 }
+
+/// Wraps `text` in the ANSI codes for red when `color` is set.
+fn paint_error(text: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[31m{}\x1b[0m", text)
+    } else {
+        text.to_string()
+    }
+}
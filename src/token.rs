@@ -1,9 +1,47 @@
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::rc::Rc;
 
 #[derive(Debug, PartialEq, Default, Clone)]
 pub struct Token {
     pub kind: TokenKind,
-    pub literal: String,
+    pub literal: Rc<str>,
+    pub position: Position,
+    /// The comments between the previous token and this one, verbatim. Only
+    /// filled in by a lexer built with [`Lexer::with_trivia`].
+    ///
+    /// [`Lexer::with_trivia`]: crate::lexer::Lexer::with_trivia
+    pub leading_trivia: Vec<String>,
+}
+
+/// Line and column (both 1-based) where a token starts in the source.
+#[derive(Debug, PartialEq, Default, Clone, Copy, Eq, Hash)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token {
+    /// Describes a character the lexer could not turn into any other token.
+    pub fn unexpected_character(&self) -> String {
+        format!("unexpected character '{}'", self.literal)
+    }
+
+    /// Describes an `Illegal` token. Its literal is either the one character
+    /// the lexer did not recognise or, for a malformed string literal, the
+    /// reason it was rejected.
+    pub fn illegal_message(&self) -> String {
+        let mut chars = self.literal.chars();
+        match (chars.next(), chars.next()) {
+            (Some(_), None) => self.unexpected_character(),
+            _ => self.literal.to_string(),
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "line {}, col {}", self.line, self.column)
+    }
 }
 
 #[derive(Debug, PartialEq, Default, Clone, Eq, Hash)]
@@ -14,6 +52,7 @@ pub enum TokenKind {
     // Identifiers + literals
     Ident,
     Int,
+    Float,
     // Operators
     Assign,
     Plus,
@@ -21,14 +60,20 @@ pub enum TokenKind {
     Bang,
     Asterisk,
     Slash,
+    Percent,
     LT,
     GT,
     EQ,
     NotEQ,
+    Pipe,
     // Delimiters
     Comma,
     Semicolon,
     Colon,
+    FatArrow,
+    Arrow,
+    Dot,
+    Ellipsis,
 
     LParen,
     RParen,
@@ -38,13 +83,105 @@ pub enum TokenKind {
     RBracket,
     // Keywords
     Function,
+    Macro,
     Let,
+    Const,
     True,
     False,
     If,
     Else,
     Return,
+    While,
+    Break,
+    Continue,
+    Match,
     String,
+    RawString,
+    Char,
+}
+
+/// The broad groups token kinds fall into. Kept private: callers ask through
+/// the `is_*` predicates on [`TokenKind`].
+#[derive(PartialEq)]
+enum Group {
+    Operator,
+    Keyword,
+    Literal,
+    Delimiter,
+    Other,
+}
+
+impl TokenKind {
+    /// `=`, arithmetic, comparison, `!` and `|>`.
+    pub fn is_operator(&self) -> bool {
+        self.group() == Group::Operator
+    }
+
+    /// The reserved words recognised by [`lookup_keywords`], `true` and `false`
+    /// included.
+    pub fn is_keyword(&self) -> bool {
+        self.group() == Group::Keyword
+    }
+
+    /// Number, string and character literals.
+    pub fn is_literal(&self) -> bool {
+        self.group() == Group::Literal
+    }
+
+    /// Brackets and separators: `( ) { } [ ] , ; : => -> . ...`.
+    pub fn is_delimiter(&self) -> bool {
+        self.group() == Group::Delimiter
+    }
+
+    // exhaustive on purpose, so a new kind cannot be added without a group
+    fn group(&self) -> Group {
+        match self {
+            TokenKind::Assign
+            | TokenKind::Plus
+            | TokenKind::Minus
+            | TokenKind::Bang
+            | TokenKind::Asterisk
+            | TokenKind::Slash
+            | TokenKind::Percent
+            | TokenKind::LT
+            | TokenKind::GT
+            | TokenKind::EQ
+            | TokenKind::NotEQ
+            | TokenKind::Pipe => Group::Operator,
+            TokenKind::Function
+            | TokenKind::Macro
+            | TokenKind::Let
+            | TokenKind::Const
+            | TokenKind::True
+            | TokenKind::False
+            | TokenKind::If
+            | TokenKind::Else
+            | TokenKind::Return
+            | TokenKind::While
+            | TokenKind::Break
+            | TokenKind::Continue
+            | TokenKind::Match => Group::Keyword,
+            TokenKind::Int
+            | TokenKind::Float
+            | TokenKind::String
+            | TokenKind::RawString
+            | TokenKind::Char => Group::Literal,
+            TokenKind::Comma
+            | TokenKind::Semicolon
+            | TokenKind::Colon
+            | TokenKind::FatArrow
+            | TokenKind::Arrow
+            | TokenKind::Dot
+            | TokenKind::Ellipsis
+            | TokenKind::LParen
+            | TokenKind::RParen
+            | TokenKind::LBrace
+            | TokenKind::RBrace
+            | TokenKind::LBracket
+            | TokenKind::RBracket => Group::Delimiter,
+            TokenKind::Illegal | TokenKind::EOF | TokenKind::Ident => Group::Other,
+        }
+    }
 }
 
 impl Display for TokenKind {
@@ -54,16 +191,19 @@ impl Display for TokenKind {
             TokenKind::EOF => write!(f, "Eof"),
             TokenKind::Ident => write!(f, "Ident"),
             TokenKind::Int => write!(f, "Int"),
+            TokenKind::Float => write!(f, "Float"),
             TokenKind::Assign => write!(f, "="),
             TokenKind::Plus => write!(f, "+"),
             TokenKind::Minus => write!(f, "-"),
             TokenKind::Bang => write!(f, "!"),
             TokenKind::Asterisk => write!(f, "*"),
             TokenKind::Slash => write!(f, "/"),
+            TokenKind::Percent => write!(f, "%"),
             TokenKind::LT => write!(f, "<"),
             TokenKind::GT => write!(f, ">"),
             TokenKind::EQ => write!(f, "=="),
             TokenKind::NotEQ => write!(f, "!="),
+            TokenKind::Pipe => write!(f, "|>"),
             TokenKind::Comma => write!(f, ","),
             TokenKind::Semicolon => write!(f, ";"),
             TokenKind::LParen => write!(f, "("),
@@ -71,16 +211,28 @@ impl Display for TokenKind {
             TokenKind::LBrace => write!(f, "{{"),
             TokenKind::RBrace => write!(f, "}}"),
             TokenKind::Function => write!(f, "Function"),
+            TokenKind::Macro => write!(f, "Macro"),
             TokenKind::Let => write!(f, "Let"),
+            TokenKind::Const => write!(f, "Const"),
             TokenKind::True => write!(f, "True"),
             TokenKind::False => write!(f, "False"),
             TokenKind::If => write!(f, "If"),
             TokenKind::Else => write!(f, "Else"),
             TokenKind::Return => write!(f, "Return"),
+            TokenKind::While => write!(f, "While"),
+            TokenKind::Break => write!(f, "Break"),
+            TokenKind::Continue => write!(f, "Continue"),
+            TokenKind::Match => write!(f, "Match"),
             TokenKind::LBracket => write!(f, "["),
             TokenKind::RBracket => write!(f, "]"),
             TokenKind::String => write!(f, "String"),
+            TokenKind::RawString => write!(f, "RawString"),
+            TokenKind::Char => write!(f, "Char"),
             TokenKind::Colon => write!(f, ":"),
+            TokenKind::FatArrow => write!(f, "=>"),
+            TokenKind::Arrow => write!(f, "->"),
+            TokenKind::Dot => write!(f, "."),
+            TokenKind::Ellipsis => write!(f, "..."),
         }
     }
 }
@@ -88,12 +240,61 @@ impl Display for TokenKind {
 pub fn lookup_keywords(identifier: &str) -> TokenKind {
     match identifier {
         "fn" => TokenKind::Function,
+        "macro" => TokenKind::Macro,
         "let" => TokenKind::Let,
+        "const" => TokenKind::Const,
         "true" => TokenKind::True,
         "false" => TokenKind::False,
         "if" => TokenKind::If,
         "else" => TokenKind::Else,
         "return" => TokenKind::Return,
+        "while" => TokenKind::While,
+        "break" => TokenKind::Break,
+        "continue" => TokenKind::Continue,
+        "match" => TokenKind::Match,
         _ => TokenKind::Ident,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{lookup_keywords, TokenKind};
+
+    #[test]
+    fn test_token_kind_categories() {
+        // (kind, operator, keyword, literal, delimiter)
+        let tests = vec![
+            (TokenKind::Plus, true, false, false, false),
+            (TokenKind::Assign, true, false, false, false),
+            (TokenKind::Percent, true, false, false, false),
+            (TokenKind::Let, false, true, false, false),
+            (TokenKind::True, false, true, false, false),
+            (TokenKind::Int, false, false, true, false),
+            (TokenKind::Char, false, false, true, false),
+            (TokenKind::LParen, false, false, false, true),
+            (TokenKind::FatArrow, false, false, false, true),
+            (TokenKind::Ident, false, false, false, false),
+            (TokenKind::EOF, false, false, false, false),
+        ];
+
+        for (kind, operator, keyword, literal, delimiter) in tests {
+            assert_eq!(kind.is_operator(), operator, "{:?}.is_operator()", kind);
+            assert_eq!(kind.is_keyword(), keyword, "{:?}.is_keyword()", kind);
+            assert_eq!(kind.is_literal(), literal, "{:?}.is_literal()", kind);
+            assert_eq!(kind.is_delimiter(), delimiter, "{:?}.is_delimiter()", kind);
+        }
+    }
+
+    #[test]
+    fn test_every_keyword_is_a_keyword() {
+        let words = [
+            "fn", "macro", "let", "const", "true", "false", "if", "else", "return", "while",
+            "break", "continue", "match",
+        ];
+
+        for word in words {
+            assert!(lookup_keywords(word).is_keyword(), "{}", word);
+        }
+        assert!(!lookup_keywords("lettuce").is_keyword());
+    }
+}
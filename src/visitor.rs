@@ -0,0 +1,596 @@
+//! Read-only traversal of the AST for tooling such as linters and analyses.
+
+use std::collections::HashSet;
+
+use crate::ast::{
+    BlockStatement, ExpressionNode, FunctionLiteral, Identifier, Program, StatementNode,
+};
+use crate::builtins::Builtins;
+use crate::evaluator::SPECIAL_FORMS;
+
+/// Visits the AST top-down. Every method defaults to visiting the node's
+/// children, so an implementation overrides only the node kinds it cares about
+/// and calls the matching `walk_*` function to keep descending.
+///
+/// `visit_identifier` sees identifiers used as expressions, including the
+/// target of an assignment, which must already be bound. Names being bound
+/// (`let` targets and function parameters) are reachable from the enclosing
+/// statement or function literal instead.
+pub trait Visitor {
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        walk_statement(self, stmt);
+    }
+
+    fn visit_block(&mut self, block: &BlockStatement) {
+        walk_block(self, block);
+    }
+
+    fn visit_expression(&mut self, expression: &ExpressionNode) {
+        walk_expression(self, expression);
+    }
+
+    fn visit_identifier(&mut self, _identifier: &Identifier) {}
+}
+
+/// Visits every top-level statement of `program` in order.
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stmt in &program.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, stmt: &StatementNode) {
+    match stmt {
+        StatementNode::Let(let_stmt) => visitor.visit_expression(&let_stmt.value),
+        StatementNode::Destructure(destructure) => visitor.visit_expression(&destructure.value),
+        StatementNode::Return(ret_stmt) => visitor.visit_expression(&ret_stmt.return_value),
+        StatementNode::Expression(exp_stmt) => visitor.visit_expression(&exp_stmt.expression),
+        StatementNode::Block(block) => visitor.visit_block(block),
+        StatementNode::While(while_stmt) => {
+            visitor.visit_expression(&while_stmt.condition);
+            visitor.visit_block(&while_stmt.body);
+        }
+        StatementNode::Break(_) | StatementNode::Continue(_) => {}
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &BlockStatement) {
+    for stmt in &block.statements {
+        visitor.visit_statement(stmt);
+    }
+}
+
+pub fn walk_expression<V: Visitor + ?Sized>(visitor: &mut V, expression: &ExpressionNode) {
+    match expression {
+        ExpressionNode::IdentifierNode(identifier) => visitor.visit_identifier(identifier),
+        ExpressionNode::Prefix(prefix_exp) => visitor.visit_expression(&prefix_exp.right),
+        ExpressionNode::Infix(inf_exp) => {
+            visitor.visit_expression(&inf_exp.left);
+            visitor.visit_expression(&inf_exp.right);
+        }
+        ExpressionNode::IfExpressionNode(if_exp) => {
+            visitor.visit_expression(&if_exp.condition);
+            visitor.visit_block(&if_exp.consequence);
+            if let Some(alternative) = &if_exp.alternative {
+                visitor.visit_block(alternative);
+            }
+        }
+        ExpressionNode::Function(fn_lit) => visitor.visit_block(&fn_lit.body),
+        ExpressionNode::Macro(macro_lit) => visitor.visit_block(&macro_lit.body),
+        ExpressionNode::BlockExp(block) => visitor.visit_block(block),
+        ExpressionNode::Match(match_exp) => {
+            visitor.visit_expression(&match_exp.subject);
+            for arm in &match_exp.arms {
+                if let Some(pattern) = &arm.pattern {
+                    visitor.visit_expression(pattern);
+                }
+                visitor.visit_expression(&arm.value);
+            }
+        }
+        ExpressionNode::Assign(assign) => {
+            visitor.visit_identifier(&assign.name);
+            visitor.visit_expression(&assign.value);
+        }
+        ExpressionNode::Call(call_exp) => {
+            visitor.visit_expression(&call_exp.function);
+            for arg in &call_exp.arguments {
+                visitor.visit_expression(arg);
+            }
+        }
+        ExpressionNode::Array(array_literal) => {
+            for element in &array_literal.elements {
+                visitor.visit_expression(element);
+            }
+        }
+        ExpressionNode::Index(index_exp) => {
+            visitor.visit_expression(&index_exp.left);
+            visitor.visit_expression(&index_exp.index);
+        }
+        ExpressionNode::Hash(hash_literal) => {
+            for (key, value) in &hash_literal.pairs {
+                visitor.visit_expression(key);
+                visitor.visit_expression(value);
+            }
+        }
+        ExpressionNode::Integer(_)
+        | ExpressionNode::Float(_)
+        | ExpressionNode::BooleanNode(_)
+        | ExpressionNode::StringExp(_)
+        | ExpressionNode::Char(_)
+        | ExpressionNode::None => {}
+    }
+}
+
+/// Size and nesting of a syntax tree, for profiling and for catching an AST
+/// that grows far faster than its source. Statements and expressions count
+/// as nodes; blocks only group statements, and grouping parentheses leave no
+/// node behind.
+pub trait TreeMetrics {
+    /// How many nodes the tree holds, including this one.
+    fn node_count(&self) -> usize;
+
+    /// How many nodes the longest path from here down to a leaf passes
+    /// through. A lone literal has depth 1; an empty program has depth 0.
+    fn depth(&self) -> usize;
+}
+
+impl TreeMetrics for Program {
+    fn node_count(&self) -> usize {
+        measure(|m| walk(m, self)).count
+    }
+
+    fn depth(&self) -> usize {
+        measure(|m| walk(m, self)).depth
+    }
+}
+
+impl TreeMetrics for StatementNode {
+    fn node_count(&self) -> usize {
+        measure(|m| m.visit_statement(self)).count
+    }
+
+    fn depth(&self) -> usize {
+        measure(|m| m.visit_statement(self)).depth
+    }
+}
+
+impl TreeMetrics for ExpressionNode {
+    fn node_count(&self) -> usize {
+        measure(|m| m.visit_expression(self)).count
+    }
+
+    fn depth(&self) -> usize {
+        measure(|m| m.visit_expression(self)).depth
+    }
+}
+
+fn measure(visit: impl FnOnce(&mut Measure)) -> Measure {
+    let mut measure = Measure::default();
+    visit(&mut measure);
+    measure
+}
+
+#[derive(Default)]
+struct Measure {
+    count: usize,
+    /// How many nodes enclose the one being visited, itself included.
+    level: usize,
+    /// The deepest `level` reached so far.
+    depth: usize,
+}
+
+impl Measure {
+    fn enter(&mut self) {
+        self.count += 1;
+        self.level += 1;
+        self.depth = self.depth.max(self.level);
+    }
+}
+
+impl Visitor for Measure {
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        self.enter();
+        walk_statement(self, stmt);
+        self.level -= 1;
+    }
+
+    fn visit_expression(&mut self, expression: &ExpressionNode) {
+        self.enter();
+        walk_expression(self, expression);
+        self.level -= 1;
+    }
+}
+
+/// Names referenced in `func`'s body that are neither its parameters nor
+/// bound by a `let` earlier in the body, looking through nested functions and
+/// respecting shadowing. A `let` inside an `if`, `while` or `{ ... }` block
+/// binds only until that block ends. Builtins show up here too, since they live in the
+/// global scope rather than the function's own.
+pub fn free_variables(func: &FunctionLiteral) -> HashSet<String> {
+    let mut analysis = FreeVariables {
+        scopes: Vec::new(),
+        free: HashSet::new(),
+    };
+    analysis.visit_function(func);
+    analysis.free
+}
+
+struct FreeVariables {
+    /// One set of bound names per enclosing function or block, innermost last.
+    scopes: Vec<HashSet<String>>,
+    free: HashSet<String>,
+}
+
+impl FreeVariables {
+    fn visit_function(&mut self, func: &FunctionLiteral) {
+        self.visit_scope(&func.parameters, &func.body);
+    }
+
+    fn visit_scope(&mut self, parameters: &[Identifier], body: &BlockStatement) {
+        let params = parameters
+            .iter()
+            .map(|param| param.value.to_string())
+            .collect();
+        self.scopes.push(params);
+        walk_block(self, body);
+        self.scopes.pop();
+    }
+
+    fn bind(&mut self, name: &Identifier) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.value.to_string());
+        }
+    }
+}
+
+impl Visitor for FreeVariables {
+    fn visit_block(&mut self, block: &BlockStatement) {
+        self.visit_scope(&[], block);
+    }
+
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        match stmt {
+            // `let f = fn() { f() }` may refer to itself, so bind before the body
+            StatementNode::Let(let_stmt)
+                if matches!(let_stmt.value, ExpressionNode::Function(_)) =>
+            {
+                self.bind(&let_stmt.name);
+                self.visit_expression(&let_stmt.value);
+            }
+            StatementNode::Let(let_stmt) => {
+                self.visit_expression(&let_stmt.value);
+                self.bind(&let_stmt.name);
+            }
+            StatementNode::Destructure(destructure) => {
+                self.visit_expression(&destructure.value);
+                for name in destructure.bound_names() {
+                    self.bind(name);
+                }
+            }
+            _ => walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &ExpressionNode) {
+        match expression {
+            ExpressionNode::Function(fn_lit) => self.visit_function(fn_lit),
+            ExpressionNode::Macro(macro_lit) => {
+                self.visit_scope(&macro_lit.parameters, &macro_lit.body)
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+
+    fn visit_identifier(&mut self, identifier: &Identifier) {
+        let bound = self
+            .scopes
+            .iter()
+            .any(|scope| scope.contains(&*identifier.value));
+        if !bound {
+            self.free.insert(identifier.value.to_string());
+        }
+    }
+}
+
+/// Reports identifiers read before any `let` that binds them, one warning
+/// per use. At the top level and within a single function body, order
+/// matters. Inside a nested function any name its enclosing scopes declare is
+/// accepted, even one declared later, because the call may happen after that
+/// `let` runs; this keeps mutually recursive named functions quiet. A `let`
+/// inside an `if`, `while` or `{ ... }` block is forgotten when the block ends.
+pub fn check_use_before_definition(program: &Program) -> Vec<String> {
+    let globals = Builtins
+        .all_builtins()
+        .into_iter()
+        .map(|(name, _)| name)
+        .chain(SPECIAL_FORMS.map(String::from))
+        .collect();
+    let mut checker = DefinitionChecker {
+        scopes: vec![Scope {
+            bound: globals,
+            declared: declared_names(&program.statements),
+            function: true,
+        }],
+        warnings: Vec::new(),
+    };
+    walk(&mut checker, program);
+    checker.warnings
+}
+
+struct Scope {
+    /// Names bound so far, in evaluation order.
+    bound: HashSet<String>,
+    /// Every name a `let` directly in this scope binds, wherever it appears.
+    declared: HashSet<String>,
+    /// A function body or the program itself, rather than a nested block.
+    function: bool,
+}
+
+struct DefinitionChecker {
+    scopes: Vec<Scope>,
+    warnings: Vec<String>,
+}
+
+impl DefinitionChecker {
+    fn visit_scope(&mut self, parameters: &[Identifier], body: &BlockStatement, function: bool) {
+        self.scopes.push(Scope {
+            bound: parameters
+                .iter()
+                .map(|param| param.value.to_string())
+                .collect(),
+            declared: declared_names(&body.statements),
+            function,
+        });
+        walk_block(self, body);
+        self.scopes.pop();
+    }
+}
+
+impl Visitor for DefinitionChecker {
+    fn visit_block(&mut self, block: &BlockStatement) {
+        self.visit_scope(&[], block, false);
+    }
+
+    fn visit_statement(&mut self, stmt: &StatementNode) {
+        walk_statement(self, stmt);
+        if let Some(scope) = self.scopes.last_mut() {
+            match stmt {
+                StatementNode::Let(let_stmt) => {
+                    scope.bound.insert(let_stmt.name.value.to_string());
+                }
+                StatementNode::Destructure(destructure) => scope
+                    .bound
+                    .extend(destructure.bound_names().map(|name| name.value.to_string())),
+                _ => {}
+            }
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &ExpressionNode) {
+        match expression {
+            ExpressionNode::Function(fn_lit) => {
+                self.visit_scope(&fn_lit.parameters, &fn_lit.body, true)
+            }
+            ExpressionNode::Macro(macro_lit) => {
+                self.visit_scope(&macro_lit.parameters, &macro_lit.body, true)
+            }
+            _ => walk_expression(self, expression),
+        }
+    }
+
+    fn visit_identifier(&mut self, identifier: &Identifier) {
+        let name = &*identifier.value;
+        // only scopes outside the innermost function may declare a name later
+        let mut outside_function = false;
+        let mut defined = false;
+        for scope in self.scopes.iter().rev() {
+            if scope.bound.contains(name) || (outside_function && scope.declared.contains(name)) {
+                defined = true;
+                break;
+            }
+            outside_function |= scope.function;
+        }
+        if !defined {
+            self.warnings.push(format!(
+                "identifier '{}' used before definition at {}",
+                name, identifier.token.position
+            ));
+        }
+    }
+}
+
+/// The names bound by `let` directly in `statements`. Blocks, functions and
+/// macros nested inside them get their own scope.
+fn declared_names(statements: &[StatementNode]) -> HashSet<String> {
+    let mut declared = HashSet::new();
+    for stmt in statements {
+        match stmt {
+            StatementNode::Let(let_stmt) => {
+                declared.insert(let_stmt.name.value.to_string());
+            }
+            StatementNode::Destructure(destructure) => {
+                declared.extend(destructure.bound_names().map(|name| name.value.to_string()))
+            }
+            _ => {}
+        }
+    }
+    declared
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::{
+        check_use_before_definition, free_variables, walk, walk_expression, TreeMetrics, Visitor,
+    };
+    use crate::ast::{ExpressionNode, Identifier, Program, StatementNode};
+    use crate::{lexer::Lexer, parser::Parser};
+
+    fn parse(input: &str) -> Program {
+        let lexer = Lexer::new(input);
+        let mut parser = Parser::new(lexer);
+        let program = parser.parse_program();
+        assert!(
+            parser.errors().is_empty(),
+            "parser errors: {:?}",
+            parser.errors()
+        );
+        program
+    }
+
+    #[derive(Default)]
+    struct IdentifierCollector {
+        names: Vec<String>,
+    }
+
+    impl Visitor for IdentifierCollector {
+        fn visit_identifier(&mut self, identifier: &Identifier) {
+            self.names.push(identifier.value.to_string());
+        }
+    }
+
+    #[test]
+    fn test_collect_identifiers() {
+        let program = parse(
+            "let x = 1; let f = fn(a) { if (a > x) { [a, {\"k\": x}] } }; while (f(x)[0]) { y }",
+        );
+
+        let mut collector = IdentifierCollector::default();
+        walk(&mut collector, &program);
+
+        assert_eq!(collector.names, vec!["a", "x", "a", "x", "f", "x", "y"]);
+    }
+
+    #[derive(Default)]
+    struct IntegerCounter {
+        count: usize,
+    }
+
+    impl Visitor for IntegerCounter {
+        fn visit_expression(&mut self, expression: &ExpressionNode) {
+            if let ExpressionNode::Integer(_) = expression {
+                self.count += 1;
+            }
+            walk_expression(self, expression);
+        }
+    }
+
+    #[test]
+    fn test_overridden_visit_keeps_walking() {
+        let program = parse("let a = 1 + 2; fn() { return -3; }(); [4][0]");
+
+        let mut counter = IntegerCounter::default();
+        walk(&mut counter, &program);
+
+        assert_eq!(counter.count, 5);
+    }
+
+    #[test]
+    fn test_tree_metrics() {
+        let program = parse("1 + 2 * 3");
+        let expression = match &program.statements[0] {
+            StatementNode::Expression(exp_stmt) => &exp_stmt.expression,
+            other => panic!("stmt not ExpressionStatement. got={:?}", other),
+        };
+        // (1 + (2 * 3)): two infix nodes over three literals
+        assert_eq!(expression.node_count(), 5);
+        assert_eq!(expression.depth(), 3);
+        assert_eq!(program.statements[0].node_count(), 6);
+        assert_eq!(program.node_count(), 6);
+        assert_eq!(program.depth(), 4);
+
+        let tests = vec![
+            ("", 0, 0),
+            ("x", 2, 2),
+            ("(((x)))", 2, 2),
+            ("1 + 2", 4, 3),
+            ("1 + (2 + 3)", 6, 4),
+            ("1 + (2 + (3 + 4))", 8, 5),
+            ("let f = fn(a) { a }; f(1)", 8, 4),
+            ("while (x) { break; }", 3, 2),
+        ];
+
+        for (input, count, depth) in tests {
+            let program = parse(input);
+            assert_eq!(program.node_count(), count, "node count of {}", input);
+            assert_eq!(program.depth(), depth, "depth of {}", input);
+        }
+    }
+
+    #[test]
+    fn test_free_variables() {
+        let tests = vec![
+            ("fn(x) { x + y }", vec!["y"]),
+            ("fn(x) { let z = 1; x + z }", vec![]),
+            ("fn(x) { fn(y) { x + y + z } }", vec!["z"]),
+            ("fn(x) { let y = y + 1; fn(x) { x + y } }", vec!["y"]),
+            ("fn() { a; let a = 1; a }", vec!["a"]),
+            ("fn() { if (true) { let q = 1; q } q }", vec!["q"]),
+            (
+                "fn() { while (c) { let q = 1; } { let r = 2; } q + r }",
+                vec!["c", "q", "r"],
+            ),
+            ("fn(q) { if (q) { let q = 1; } q }", vec![]),
+            (
+                "fn() { let f = fn(n) { f(n - 1) }; f(len(xs)) }",
+                vec!["len", "xs"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let program = parse(input);
+            let func = match &program.statements[0] {
+                StatementNode::Expression(exp_stmt) => match &exp_stmt.expression {
+                    ExpressionNode::Function(fn_lit) => fn_lit,
+                    other => panic!("expression is not a function. got={:?}", other),
+                },
+                other => panic!("statement is not ExpressionStatement. got={:?}", other),
+            };
+
+            let expected: HashSet<String> = expected.into_iter().map(String::from).collect();
+            assert_eq!(free_variables(func), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_use_before_definition() {
+        let tests = vec![
+            (
+                "let a = b + 1;\nlet b = 2;",
+                vec!["identifier 'b' used before definition at line 1, col 9"],
+            ),
+            (
+                "let f = fn(x) { y + x; let y = 1; };",
+                vec!["identifier 'y' used before definition at line 1, col 17"],
+            ),
+            ("let c = 1; let d = len([c]); d", vec![]),
+            (
+                "let f = fn(x) { if (x) { let y = 1; } g(x) }; let g = fn(x) { f(x) }; f(1)",
+                vec![],
+            ),
+            ("let i = 0; while (i < 3) { i = i + 1; }", vec![]),
+            (
+                "let f = fn() { if (true) { let q = 1; } q };",
+                vec!["identifier 'q' used before definition at line 1, col 41"],
+            ),
+            (
+                "{ let a = 1; a }; a",
+                vec!["identifier 'a' used before definition at line 1, col 19"],
+            ),
+            (
+                "let f = fn() { if (c) { let y = 1; } fn() { y } }; let c = 1;",
+                vec!["identifier 'y' used before definition at line 1, col 45"],
+            ),
+            (
+                "let f = fn() { if (true) { g() } let g = fn() { 1 }; };",
+                vec!["identifier 'g' used before definition at line 1, col 28"],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let warnings = check_use_before_definition(&parse(input));
+            assert_eq!(warnings, expected, "input: {}", input);
+        }
+    }
+}
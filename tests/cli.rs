@@ -0,0 +1,74 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Runs the built `guedzlang` binary with `args` and returns its exit code,
+/// stdout and stderr.
+fn run_binary(args: &[&str]) -> (Option<i32>, String, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_guedzlang"))
+        .args(args)
+        .output()
+        .expect("failed to run guedzlang");
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).expect("stdout is not utf-8"),
+        String::from_utf8(output.stderr).expect("stderr is not utf-8"),
+    )
+}
+
+#[test]
+fn eval_flag_prints_the_result() {
+    let (code, stdout, stderr) = run_binary(&["--eval", "let x = 2; x + 2"]);
+    assert_eq!(code, Some(0), "stderr: {stderr}");
+    assert_eq!(stdout, "4\n");
+}
+
+#[test]
+fn eval_flag_reports_errors() {
+    let (code, stdout, stderr) = run_binary(&["--eval", "missing"]);
+    assert_eq!(code, Some(3));
+    assert_eq!(stdout, "");
+    assert_eq!(
+        stderr,
+        "ERROR: [line 1, col 1] identifier not found: missing\n"
+    );
+}
+
+#[test]
+fn parse_errors_exit_with_2() {
+    let (code, stdout, stderr) = run_binary(&["--eval", "let = 1;"]);
+    assert_eq!(code, Some(2));
+    assert_eq!(stdout, "");
+    assert!(
+        stderr.starts_with("expected next token"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn unknown_arguments_print_usage() {
+    let (code, _, stderr) = run_binary(&["--nope"]);
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("usage: guedzlang"), "stderr: {stderr}");
+}
+
+#[test]
+fn dash_reads_the_program_from_stdin() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_guedzlang"))
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to run guedzlang");
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(b"1 + 2\n")
+        .expect("failed to write stdin");
+
+    let output = child
+        .wait_with_output()
+        .expect("failed to wait on guedzlang");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+}
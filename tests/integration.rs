@@ -1,8 +1,9 @@
-
+use guedzlang::ast::{ExpressionNode, Identifier};
 use guedzlang::evaluator::Evaluator;
 use guedzlang::lexer::Lexer;
 use guedzlang::object::Object;
 use guedzlang::parser::Parser;
+use guedzlang::token::TokenKind;
 
 /// Lex -> parse -> eval a source string, asserting it parses without errors.
 fn run(input: &str) -> Object {
@@ -62,3 +63,22 @@ fn arrays_with_push_and_indexing() {
 fn line_comments_are_ignored() {
     expect_integer("let x = 41; // this is a comment\n x + 1;", 42);
 }
+
+#[test]
+fn custom_prefix_rules_can_be_registered() {
+    // `@` is otherwise an illegal character; here it reads as the name `me`
+    fn parse_at(parser: &mut Parser) -> ExpressionNode {
+        ExpressionNode::IdentifierNode(Identifier {
+            token: parser.cur_token.clone(),
+            value: "me".into(),
+        })
+    }
+
+    let mut parser = Parser::new(Lexer::new("let me = 41; @ + 1"));
+    parser.register_prefix(TokenKind::Illegal, parse_at);
+    let program = parser.parse_program();
+
+    assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+    assert_eq!(program.to_string(), "let me = 41;(me + 1)");
+    assert_eq!(Evaluator::new().eval_program(program), Object::Integer(42));
+}
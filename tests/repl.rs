@@ -0,0 +1,213 @@
+use std::{env, fs, io::Cursor};
+
+use guedzlang::repl::{self, ReplConfig};
+
+/// Feeds `input` to a REPL session and returns everything it wrote.
+fn run_session(input: &str) -> String {
+    run_session_with(input, &ReplConfig::default())
+}
+
+fn run_session_with(input: &str, config: &ReplConfig) -> String {
+    let mut output = Vec::new();
+    repl::run(Cursor::new(input.as_bytes()), &mut output, config).expect("repl session failed");
+    String::from_utf8(output).expect("repl wrote invalid utf-8")
+}
+
+#[test]
+fn evaluates_lines_and_keeps_bindings() {
+    let output = run_session("let x = 5;\nx * 2\n\"hi\"\n");
+    assert_eq!(output, ">> 5\n>> 10\n>> \"hi\"\n>> Exiting REPL...\n");
+}
+
+#[test]
+fn reports_parse_errors_and_keeps_going() {
+    let output = run_session("let = 1;\n1 + 1\n");
+    assert!(
+        output.starts_with(">> Oops! We ran into parser errors\n"),
+        "got: {output}"
+    );
+    assert!(
+        output.ends_with(">> 2\n>> Exiting REPL...\n"),
+        "got: {output}"
+    );
+}
+
+#[test]
+fn last_reruns_the_previous_input() {
+    let output = run_session("let x = 1;\nlet x = x + 1;\n:last\nx\n");
+    assert_eq!(output, ">> 1\n>> 2\n>> 3\n>> 3\n>> Exiting REPL...\n");
+}
+
+#[test]
+fn history_lists_past_inputs() {
+    let output = run_session("1 + 1\n\n\"a\"\n:history\n");
+    assert_eq!(
+        output,
+        ">> 2\n>> >> \"a\"\n>> 1: 1 + 1\n2: \"a\"\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn blank_lines_prompt_again() {
+    let output = run_session("\n   \n\t\n1\n");
+    assert_eq!(output, ">> >> >> >> 1\n>> Exiting REPL...\n");
+}
+
+#[test]
+fn json_toggles_structured_output() {
+    let output = run_session("1 + 1\n:json\n[1, \"a\"]\n:json\n\"a\"\n");
+    assert_eq!(
+        output,
+        ">> 2\n>> JSON output on\n\
+         >> {\"type\":\"ARRAY\",\"value\":[{\"type\":\"INTEGER\",\"value\":1},{\"type\":\"STRING\",\"value\":\"a\"}]}\n\
+         >> JSON output off\n>> \"a\"\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn last_without_history() {
+    assert_eq!(
+        run_session(":last\n"),
+        ">> No history yet\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn env_lists_bindings_sorted_by_name() {
+    let output = run_session("let name = \"ada\";\nlet age = 36;\n:env\n");
+    assert_eq!(
+        output,
+        ">> \"ada\"\n>> 36\n>> age = 36 (INTEGER)\nname = ada (STRING)\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn load_evaluates_a_file_into_the_session() {
+    let path = env::temp_dir().join(format!("guedzlang-load-{}.gz", std::process::id()));
+    fs::write(&path, "let answer = 42;\nlet double = fn(x) { x * 2 };\n").unwrap();
+
+    let output = run_session(&format!(
+        ":load {}\ndouble(answer) - answer\n",
+        path.display()
+    ));
+    fs::remove_file(&path).unwrap();
+
+    assert!(
+        output.ends_with(">> 42\n>> Exiting REPL...\n"),
+        "got: {output}"
+    );
+}
+
+#[test]
+fn load_reports_missing_files_and_keeps_going() {
+    let output = run_session(":load /definitely/not/here.gz\n1 + 1\n");
+    assert!(
+        output.starts_with(">> Could not load /definitely/not/here.gz: "),
+        "got: {output}"
+    );
+    assert!(
+        output.ends_with(">> 2\n>> Exiting REPL...\n"),
+        "got: {output}"
+    );
+}
+
+#[test]
+fn prompt_is_configurable() {
+    let config = ReplConfig {
+        prompt: String::from("guedz> "),
+        ..ReplConfig::default()
+    };
+    let output = run_session_with("1 + 1\n", &config);
+    assert_eq!(output, "guedz> 2\nguedz> Exiting REPL...\n");
+}
+
+#[test]
+fn errors_are_red_only_when_color_is_enabled() {
+    let input = "let = 1;\nmissing\n1\n";
+
+    let plain = run_session(input);
+    assert!(!plain.contains('\x1b'), "got: {plain}");
+
+    let config = ReplConfig {
+        color: true,
+        ..ReplConfig::default()
+    };
+    let colored = run_session_with(input, &config);
+    assert!(
+        colored.starts_with(">> \x1b[31mOops! We ran into parser errors\x1b[0m\n"),
+        "got: {colored}"
+    );
+    assert!(
+        colored
+            .contains(">> \x1b[31mERROR: [line 1, col 1] identifier not found: missing\x1b[0m\n"),
+        "got: {colored}"
+    );
+    // ordinary results are left alone
+    assert!(
+        colored.ends_with(">> 1\n>> Exiting REPL...\n"),
+        "got: {colored}"
+    );
+}
+
+#[test]
+fn errors_caught_by_try_are_not_red() {
+    let config = ReplConfig {
+        color: true,
+        ..ReplConfig::default()
+    };
+    let output = run_session_with("try(fn() { 1 / 0 })\n", &config);
+    assert_eq!(
+        output,
+        ">> ERROR: [line 1, col 14] division by zero\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn time_reports_parse_and_eval_durations() {
+    let output = run_session(":time\n1 + 1\n:time\n2\n");
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert_eq!(lines[0], ">> Timing on");
+    assert_eq!(lines[1], ">> 2");
+    assert!(
+        lines[2].starts_with("parsed in ") && lines[2].contains(", evaluated in "),
+        "got: {output}"
+    );
+    assert_eq!(&lines[3..], [">> Timing off", ">> 2", ">> Exiting REPL..."]);
+}
+
+#[test]
+fn help_lists_commands_and_builtins() {
+    let output = run_session(":help\n");
+    assert!(output.contains(":quit"), "got: {output}");
+    assert!(output.contains(":load <path>"), "got: {output}");
+    assert!(output.contains("len"), "got: {output}");
+}
+
+#[test]
+fn quit_ends_the_session() {
+    let output = run_session("1\n:quit\n2\n");
+    assert_eq!(output, ">> 1\n>> Exiting REPL...\n");
+}
+
+#[test]
+fn crlf_line_endings_are_stripped() {
+    let output = run_session("let s = \"a\";\r\ns + \"b\"\r\n:history\r\n");
+    assert_eq!(
+        output,
+        ">> \"a\"\n>> \"ab\"\n>> 1: let s = \"a\";\n2: s + \"b\"\n>> Exiting REPL...\n"
+    );
+}
+
+#[test]
+fn unclosed_brackets_are_a_parse_error() {
+    let output = run_session("(1 +\n2\n");
+    assert!(
+        output.starts_with(">> Oops! We ran into parser errors\n"),
+        "got: {output}"
+    );
+    assert!(
+        output.ends_with(">> 2\n>> Exiting REPL...\n"),
+        "got: {output}"
+    );
+}